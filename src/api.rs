@@ -1,9 +1,10 @@
 use actix_web::{
     delete, get, patch, post,
-    web::{Data, Json, Path},
-    HttpResponse,
+    web::{Bytes, Data, Json, Path},
+    HttpRequest, HttpResponse,
 };
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde_json::json;
 use std::cmp::Ordering;
 use std::{
@@ -12,6 +13,23 @@ use std::{
 };
 use uuid::Uuid;
 
+use crate::audit::{actor, AuditEntry, Operation};
+use crate::conflicts::{
+    conflict_response, find_conflicts, reschedules, with_conflict_warning, ConflictConfig,
+    ConflictMode,
+};
+use crate::integrity::{unknown_reference, KnownNames};
+use crate::merge_patch::{merge_patch, parse_merge_patch};
+use crate::names::normalize_name;
+use crate::numbering::{
+    check_number_unchanged, insert_numbered, legacy_number, optional_number, remove_numbered,
+    required_number,
+};
+use crate::preconditions::{check_if_match, etag, versioned_response};
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{AuditRepo, HostRepo, InstructorRepo, TrashRepo};
+use crate::workflow::{check_consistency, check_transition};
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub enum Language {
     Any,
@@ -24,7 +42,7 @@ pub enum Language {
     Persian,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Status {
     Planning,
     Invited,
@@ -48,19 +66,277 @@ pub enum FlyerStatus {
     Complete,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct EngagementQuery {
-    pub language: Option<Language>,
-    pub number: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub language: Option<Vec<Language>>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub number: Option<usize>,
     pub activity_type: Option<String>,
-    pub instructor: Option<String>,
-    pub host: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub instructor: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub host: Option<Vec<String>>,
     pub date: Option<String>,
-    pub status: Option<Status>,
-    pub host_status: Option<HostStatus>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    // Every whitespace-separated token must appear in the title, notes,
+    // instructor or host
+    pub q: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub status: Option<Vec<Status>>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub host_status: Option<Vec<HostStatus>>,
     pub flyer_status: Option<FlyerStatus>,
 }
 
+impl EngagementQuery {
+    pub fn from_query_string(query: &str) -> Result<Self, String> {
+        let query: Self =
+            serde_html_form::from_str(query).map_err(|e| format!("Invalid query string: {}", e))?;
+        query.validate()?;
+        Ok(query)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for bound in [&self.date_from, &self.date_to].into_iter().flatten() {
+            NaiveDate::parse_from_str(bound, "%Y-%m-%d").map_err(|_| {
+                format!(
+                    "Invalid date format: {}. Expected format: YYYY-MM-DD",
+                    bound
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn matches(&self, x: &Engagement) -> bool {
+        self.language.as_ref().is_none_or(|langs| {
+            langs
+                .iter()
+                .any(|lang| matches!(lang, Language::Any) || x.language == *lang)
+        }) && self
+            .number
+            .as_ref()
+            .is_none_or(|q_num| x.number == Some(*q_num))
+            && self
+                .activity_type
+                .as_ref()
+                .is_none_or(|q_act| x.activity_type.as_ref() == Some(q_act))
+            && self
+                .instructor
+                .as_ref()
+                .is_none_or(|q_inst| q_inst.contains(&x.instructor))
+            && self
+                .host
+                .as_ref()
+                .is_none_or(|q_host| q_host.contains(&x.host))
+            && self.date.as_ref().is_none_or(|q_date| x.date == *q_date)
+            && self.date_from.as_ref().is_none_or(|from| x.date >= *from)
+            && self.date_to.as_ref().is_none_or(|to| x.date <= *to)
+            && self.q.as_ref().is_none_or(|q| matches_text(x, q))
+            && self
+                .status
+                .as_ref()
+                .is_none_or(|q_status| q_status.contains(&x.status))
+            && self.host_status.as_ref().is_none_or(|q_host_status| {
+                x.host_status
+                    .as_ref()
+                    .is_some_and(|host_status| q_host_status.contains(host_status))
+            })
+            && self
+                .flyer_status
+                .as_ref()
+                .is_none_or(|q_flyer_status| x.flyer_status.as_ref() == Some(q_flyer_status))
+    }
+}
+
+fn matches_text(x: &Engagement, q: &str) -> bool {
+    let haystack = [
+        x.title.as_str(),
+        x.notes.as_deref().unwrap_or_default(),
+        x.instructor.as_str(),
+        x.host.as_str(),
+    ]
+    .join("\n")
+    .to_lowercase();
+
+    q.to_lowercase()
+        .split_whitespace()
+        .all(|token| haystack.contains(token))
+}
+
+// Accepts one value or a list, so both host=A&host=B and the legacy JSON
+// body's "host": "A" deserialize
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    let value: Option<OneOrMany<T>> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(value.map(|value| match value {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    }))
+}
+
+// A JSON body is still accepted for older clients but is deprecated. Paging
+// and field parameters are not filters, so they may accompany a body.
+pub fn resolve_engagement_query(
+    req: &HttpRequest,
+    body: Option<Json<EngagementQuery>>,
+) -> Result<EngagementQuery, HttpResponse> {
+    let query = EngagementQuery::from_query_string(req.query_string()).map_err(invalid_query)?;
+
+    match body {
+        Some(_) if query != EngagementQuery::default() => Err(invalid_query(
+            "Filters must be given either as query parameters or as a JSON body, not both"
+                .to_string(),
+        )),
+        Some(body) => {
+            log::warn!("GET /engs called with a JSON body; use query parameters instead");
+            let query = body.into_inner();
+            query.validate().map_err(invalid_query)?;
+            Ok(query)
+        }
+        None => Ok(query),
+    }
+}
+
+pub fn invalid_query(details: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Invalid query",
+            "details": details
+        }))
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    Date,
+    Instructor,
+    Host,
+    Status,
+    Number,
+    Title,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct ListOptions {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    pub sort: Option<SortKey>,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub fields: Option<Vec<String>>,
+    pub tz: Option<String>,
+}
+
+impl ListOptions {
+    pub fn from_query_string(query: &str) -> Result<Self, String> {
+        let options: Self =
+            serde_html_form::from_str(query).map_err(|e| format!("Invalid list options: {}", e))?;
+        options.tz.as_deref().map(parse_timezone).transpose()?;
+        Ok(options)
+    }
+
+    // Ties fall back to the engagement number so pages stay stable
+    pub fn sort(&self, engagements: &mut [Engagement]) {
+        engagements.sort_by(|a, b| {
+            let ordering = match self.sort {
+                Some(SortKey::Date) => a.date.cmp(&b.date),
+                Some(SortKey::Instructor) => compare_text(&a.instructor, &b.instructor),
+                Some(SortKey::Host) => compare_text(&a.host, &b.host),
+                Some(SortKey::Status) => a.status.cmp(&b.status),
+                Some(SortKey::Title) => compare_text(&a.title, &b.title),
+                Some(SortKey::Number) | None => Ordering::Equal,
+            }
+            .then_with(|| compare_by_number(a, b))
+            .then_with(|| a.id.cmp(&b.id));
+
+            match self.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+    }
+
+    // `id` is always kept when fields are selected
+    pub fn page(
+        &self,
+        engagements: &[Engagement],
+    ) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+        let tz = self.tz.as_deref().and_then(|tz| parse_timezone(tz).ok());
+        let fields: Option<Vec<&str>> = self.fields.as_ref().map(|fields| {
+            fields
+                .iter()
+                .flat_map(|f| f.split(','))
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .collect()
+        });
+
+        engagements
+            .iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|eng| {
+                let mut value = serde_json::to_value(eng)?;
+                if let (Some(fields), Some(object)) = (fields.as_ref(), value.as_object_mut()) {
+                    object.retain(|key, _| key == "id" || fields.contains(&key.as_str()));
+                }
+                if let (Some(tz), Some(object)) = (tz, value.as_object_mut()) {
+                    if let Some(starts_at) = eng.starts_at() {
+                        object.insert(
+                            "starts_at".to_string(),
+                            starts_at.with_timezone(&tz).to_rfc3339().into(),
+                        );
+                    }
+                    if let Some(ends_at) = eng.ends_at() {
+                        object.insert(
+                            "ends_at".to_string(),
+                            ends_at.with_timezone(&tz).to_rfc3339().into(),
+                        );
+                    }
+                }
+                Ok(value)
+            })
+            .collect()
+    }
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    a.to_lowercase().cmp(&b.to_lowercase())
+}
+
+fn compare_by_number(a: &Engagement, b: &Engagement) -> Ordering {
+    match (a.number, b.number) {
+        (Some(num_a), Some(num_b)) => num_a.cmp(&num_b),
+        (Some(_), None) => Ordering::Less, // a has a number, b doesn't - a comes first
+        (None, Some(_)) => Ordering::Greater, // b has a number, a doesn't - b comes first
+        (None, None) => a.date.cmp(&b.date), // Neither has a number, compare by date
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct Engagement {
     pub id: Uuid,
@@ -78,16 +354,80 @@ pub struct Engagement {
     pub flyer_status: Option<FlyerStatus>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub number: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "legacy_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub number: Option<usize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub activity_type: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_updated_by: Option<String>,
+    // Local times (HH:MM) in timezone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+    // IANA name, e.g. Europe/Madrid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<Uuid>,
+    #[serde(default)]
+    pub version: u64,
+}
+
+// Times must exist on that date, i.e. not fall into a daylight saving gap
+fn validate_schedule(
+    date: &str,
+    start_time: Option<&str>,
+    end_time: Option<&str>,
+    timezone: Option<&str>,
+) -> Result<(), String> {
+    let tz = timezone.map(parse_timezone).transpose()?;
+
+    let (start, end) = match (start_time, end_time) {
+        (None, None) => return Ok(()),
+        (None, Some(_)) => return Err("An end time requires a start time".to_string()),
+        (Some(start), end) => (start, end),
+    };
+
+    let tz = tz.ok_or("A timezone is required when start or end times are given")?;
+
+    let start = local_datetime(date, start, tz)?;
+    if let Some(end) = end {
+        let end = local_datetime(date, end, tz)?;
+        if end <= start {
+            return Err("End time must be after start time".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>().map_err(|_| {
+        format!(
+            "Unknown timezone: {}. Expected an IANA name such as Europe/Madrid",
+            name
+        )
+    })
+}
+
+fn local_datetime(date: &str, time: &str, tz: Tz) -> Result<DateTime<Tz>, String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date format: {}. Expected format: YYYY-MM-DD", date))?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("Invalid time format: {}. Expected format: HH:MM", time))?;
+
+    tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| format!("{} {} does not exist in {}", date, time, tz))
 }
 
 impl Engagement {
-    fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), String> {
         NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").map_err(|_| {
             format!(
                 "Invalid date format: {}. Expected format: YYYY-MM-DD",
@@ -95,6 +435,13 @@ impl Engagement {
             )
         })?;
 
+        validate_schedule(
+            &self.date,
+            self.start_time.as_deref(),
+            self.end_time.as_deref(),
+            self.timezone.as_deref(),
+        )?;
+
         if self.part == 0 {
             return Err("Part number must be greater than 0".to_string());
         }
@@ -113,11 +460,11 @@ impl Engagement {
         Ok(())
     }
 
-    fn clean(&self) -> Self {
+    pub fn clean(&self) -> Self {
         Self {
             id: self.id,
-            instructor: ammonia::clean(&self.instructor),
-            host: ammonia::clean(&self.host),
+            instructor: normalize_name(&self.instructor),
+            host: normalize_name(&self.host),
             date: ammonia::clean(&self.date),
             language: self.language.clone(),
             title: ammonia::clean(&self.title),
@@ -127,17 +474,35 @@ impl Engagement {
             host_status: self.host_status.clone(),
             flyer_status: self.flyer_status.clone(),
             notes: Some(ammonia::clean(
-                self.notes.clone().unwrap_or(String::new()).as_str(),
-            )),
-            number: Some(ammonia::clean(
-                self.number.clone().unwrap_or_default().as_str(),
+                self.notes.clone().unwrap_or_default().as_str(),
             )),
+            number: self.number,
             activity_type: Some(ammonia::clean(
                 self.activity_type.clone().unwrap_or_default().as_str(),
             )),
             last_updated_by: self.last_updated_by.clone(),
+            start_time: self.start_time.clone(),
+            end_time: self.end_time.clone(),
+            timezone: self.timezone.clone(),
+            series_id: self.series_id,
+            version: self.version,
         }
     }
+
+    pub fn starts_at(&self) -> Option<DateTime<Utc>> {
+        self.instant(self.start_time.as_deref()?)
+    }
+
+    pub fn ends_at(&self) -> Option<DateTime<Utc>> {
+        self.instant(self.end_time.as_deref()?)
+    }
+
+    fn instant(&self, time: &str) -> Option<DateTime<Utc>> {
+        let tz = parse_timezone(self.timezone.as_deref()?).ok()?;
+        local_datetime(&self.date, time, tz)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
 }
 
 impl std::hash::Hash for Engagement {
@@ -167,13 +532,20 @@ pub struct NewEngagement {
     pub host_status: HostStatus,
     pub flyer_status: FlyerStatus,
     pub notes: String,
-    pub number: String,
+    #[serde(deserialize_with = "required_number")]
+    pub number: usize,
     pub activity_type: String,
     pub last_updated_by: String,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 impl NewEngagement {
-    fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), String> {
         NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").map_err(|_| {
             format!(
                 "Invalid date format: {}. Expected format: YYYY-MM-DD",
@@ -181,6 +553,13 @@ impl NewEngagement {
             )
         })?;
 
+        validate_schedule(
+            &self.date,
+            self.start_time.as_deref(),
+            self.end_time.as_deref(),
+            self.timezone.as_deref(),
+        )?;
+
         if self.part == 0 {
             return Err("Part number must be greater than 0".to_string());
         }
@@ -196,13 +575,53 @@ impl NewEngagement {
             ));
         }
 
+        if self.number == 0 {
+            return Err("Number must be greater than 0".to_string());
+        }
+
+        check_consistency(&self.status, Some(&self.host_status), &self.date)?;
+
         Ok(())
     }
+
+    pub fn to_engagement(&self) -> Engagement {
+        Engagement {
+            id: Uuid::new_v4(),
+            instructor: normalize_name(&self.instructor),
+            host: normalize_name(&self.host),
+            date: ammonia::clean(&self.date),
+            language: self.language.clone(),
+            title: ammonia::clean(&self.title),
+            part: self.part,
+            num_parts: self.num_parts,
+            status: self.status.clone(),
+            host_status: Some(self.host_status.clone()),
+            flyer_status: Some(self.flyer_status.clone()),
+            notes: Some(ammonia::clean(&self.notes)),
+            number: Some(self.number),
+            activity_type: Some(self.activity_type.clone()),
+            last_updated_by: Some(format!(
+                "{}  {}",
+                self.last_updated_by.clone(),
+                chrono::Utc::now().format("%Y-%m-%d")
+            )),
+            start_time: self.start_time.clone(),
+            end_time: self.end_time.clone(),
+            timezone: self.timezone.clone(),
+            series_id: None,
+            version: 1,
+        }
+    }
 }
 
 #[post("/engs")]
 pub async fn add_eng(
     repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
     body: Json<NewEngagement>,
 ) -> Result<HttpResponse, actix_web::Error> {
     if let Err(validation_error) = body.validate() {
@@ -215,139 +634,58 @@ pub async fn add_eng(
     }
 
     // Create the new engagement outside the lock
-    let new_eng = Engagement {
-        id: Uuid::new_v4(),
-        instructor: ammonia::clean(&body.instructor),
-        host: ammonia::clean(&body.host),
-        date: ammonia::clean(&body.date),
-        language: body.language.clone(),
-        title: ammonia::clean(&body.title),
-        part: body.part,
-        num_parts: body.num_parts,
-        status: body.status.clone(),
-        host_status: Some(body.host_status.clone()),
-        flyer_status: Some(body.flyer_status.clone()),
-        notes: Some(ammonia::clean(&body.notes)),
-        number: Some(ammonia::clean(&body.number)),
-        activity_type: Some(body.activity_type.clone()),
-        last_updated_by: Some(format!(
-            "{}  {}",
-            body.last_updated_by.clone(),
-            chrono::Utc::now().format("%Y-%m-%d")
-        )),
-    };
+    let new_eng = body.to_engagement();
+    let actor = actor(&req, Some(&body.last_updated_by));
 
     let mut repo_guard = repo
         .lock()
         .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
-
-    // Check if number exists and collect engagements to update
-    let num = body.number.parse::<usize>().map_err(|e| {
-        actix_web::error::ErrorInternalServerError(format!("Invalid number format: {}", e))
-    })?;
-
-    let existing_numbers: Vec<_> = repo_guard
-        .iter()
-        .filter_map(|eng| {
-            eng.number
-                .as_ref()
-                .and_then(|n| n.parse::<usize>().ok())
-                .map(|n| (eng.clone(), n))
-        })
-        .collect();
-
-    let number_exists = existing_numbers.iter().any(|(_, n)| *n == num);
-
-    if number_exists {
-        // Update numbers in a single pass
-        let to_update: Vec<_> = existing_numbers
-            .into_iter()
-            .filter(|(_, existing)| *existing >= num)
-            .map(|(eng, _)| eng)
-            .collect();
-
-        for eng in to_update {
-            repo_guard.remove(&eng);
-            let mut updated = eng;
-            if let Some(ref mut curr_num) = updated.number {
-                if let Ok(existing_num) = curr_num.parse::<usize>() {
-                    *curr_num = (existing_num + 1).to_string();
-                }
-            }
-            repo_guard.insert(updated);
-        }
+    if let Err(reference_error) = KnownNames::load(&instructors, &hosts)?.check(None, &new_eng) {
+        return Ok(unknown_reference(reference_error));
+    }
+    let conflicts = find_conflicts(repo_guard.iter(), &new_eng);
+    if !conflicts.is_empty() && conflict_config.mode == ConflictMode::Reject {
+        return Ok(conflict_response(&conflicts));
     }
 
-    repo_guard.insert(new_eng);
+    let mut audit_guard = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?;
+
+    insert_numbered(&mut repo_guard, &mut audit_guard, &actor, new_eng);
 
-    Ok(HttpResponse::Created().finish())
+    Ok(with_conflict_warning(HttpResponse::Created(), &conflicts))
 }
 
 #[get("/engs")]
 pub async fn get_engs(
     repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
-    body: Json<EngagementQuery>,
+    req: HttpRequest,
+    body: Option<Json<EngagementQuery>>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    let options = match ListOptions::from_query_string(req.query_string()) {
+        Ok(options) => options,
+        Err(e) => return Ok(invalid_query(e)),
+    };
+    let query = match resolve_engagement_query(&req, body) {
+        Ok(query) => query,
+        Err(response) => return Ok(response),
+    };
+
     match repo.lock() {
         Ok(repo) => {
-            let mut engagements: Vec<Engagement> = repo
-                .iter()
-                .filter(|x| {
-                    body.language.as_ref().map_or(true, |lang| {
-                        matches!(lang, Language::Any) || x.language == *lang
-                    }) && body.number.as_ref().map_or(true, |q_num| {
-                        x.number.as_ref().map_or(false, |x_num| x_num == q_num)
-                    }) && body.activity_type.as_ref().map_or(true, |q_act| {
-                        x.activity_type
-                            .as_ref()
-                            .map_or(false, |x_act| x_act == q_act)
-                    }) && body
-                        .instructor
-                        .as_ref()
-                        .map_or(true, |q_inst| x.instructor == *q_inst)
-                        && body.host.as_ref().map_or(true, |q_host| x.host == *q_host)
-                        && body.date.as_ref().map_or(true, |q_date| x.date == *q_date)
-                        && body
-                            .status
-                            .as_ref()
-                            .map_or(true, |q_status| x.status == *q_status)
-                        && body.host_status.as_ref().map_or(true, |q_host_status| {
-                            x.host_status
-                                .as_ref()
-                                .map_or(false, |x_host_status| x_host_status == q_host_status)
-                        })
-                        && body.flyer_status.as_ref().map_or(true, |q_flyer_status| {
-                            x.flyer_status
-                                .as_ref()
-                                .map_or(false, |x_flyer_status| x_flyer_status == q_flyer_status)
-                        })
-                })
-                .cloned()
-                .collect();
-
-            engagements.sort_by(|a, b| {
-                match (a.number.as_ref(), b.number.as_ref()) {
-                    (Some(num_a), Some(num_b)) => {
-                        // Both have numbers, compare them.  Handle potential parsing errors.
-                        let num_a_parsed = num_a.parse::<usize>();
-                        let num_b_parsed = num_b.parse::<usize>();
-
-                        match (num_a_parsed, num_b_parsed) {
-                            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num), // Compare parsed numbers
-                            (Ok(_), Err(_)) => Ordering::Less, // a is a valid number, b is not - a comes first
-                            (Err(_), Ok(_)) => Ordering::Greater, // b is a valid number, a is not - b comes first
-                            (Err(_), Err(_)) => num_a.cmp(num_b), // Both are invalid numbers, compare as strings
-                        }
-                    }
-                    (Some(_), None) => Ordering::Less, // a has a number, b doesn't - a comes first
-                    (None, Some(_)) => Ordering::Greater, // b has a number, a doesn't - b comes first
-                    (None, None) => a.date.cmp(&b.date),  // Neither has a number, compare by date
-                }
-            });
+            let mut engagements: Vec<Engagement> =
+                repo.iter().filter(|x| query.matches(x)).cloned().collect();
+            options.sort(&mut engagements);
+
+            let page = options
+                .page(&engagements)
+                .map_err(actix_web::error::ErrorInternalServerError)?;
 
             Ok(HttpResponse::Ok()
                 .content_type("application/json; charset=utf-8")
-                .json(engagements))
+                .insert_header(("X-Total-Count", engagements.len()))
+                .json(page))
         }
         Err(_) => Err(actix_web::error::ErrorInternalServerError(
             "Failed to acquire repo lock (GET)",
@@ -355,12 +693,34 @@ pub async fn get_engs(
     }
 }
 
-#[patch("/engs")]
-pub async fn edit_eng(
+#[get("/engs/{id}")]
+pub async fn get_eng(
     repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
-    body: Json<Engagement>,
+    req: HttpRequest,
+    path: Path<Uuid>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    if let Err(validation_error) = body.validate() {
+    let target_id = path.into_inner();
+
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    match repo_guard.iter().find(|e| e.id == target_id) {
+        Some(eng) => Ok(versioned_response(&req, eng, eng.version)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+// Shared by PATCH /engs and the merge patch PATCH /engs/{id}
+fn update_engagement(
+    repo: &mut HashSet<Engagement>,
+    audit: &AuditRepo,
+    known: &KnownNames,
+    conflict_config: &ConflictConfig,
+    req: &HttpRequest,
+    edited: Engagement,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(validation_error) = edited.validate() {
         return Ok(HttpResponse::BadRequest()
             .content_type("application/json")
             .json(json!({
@@ -369,80 +729,179 @@ pub async fn edit_eng(
             })));
     }
 
-    match repo.lock() {
-        Ok(mut repo) => {
-            let mut target_eng = body.into_inner().clean();
-            let update_string = target_eng.last_updated_by.clone();
-            target_eng.last_updated_by = Some(format!(
-                "{} {}",
-                update_string.unwrap_or_default(),
-                chrono::Utc::now().format("%Y-%m-%d")
-            ));
-            if repo.contains(&target_eng) {
-                repo.remove(&target_eng);
-                repo.insert(target_eng.clone());
-                Ok(HttpResponse::Ok().finish())
-            } else {
-                Ok(HttpResponse::NotFound().finish())
-            }
+    let mut target_eng = edited.clean();
+    let update_string = target_eng.last_updated_by.clone();
+    let actor = actor(req, update_string.as_deref());
+    target_eng.last_updated_by = Some(format!(
+        "{} {}",
+        update_string.unwrap_or_default(),
+        chrono::Utc::now().format("%Y-%m-%d")
+    ));
+
+    let Some(current) = repo.get(&target_eng) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    if let Err(response) = check_if_match(req, current, current.version) {
+        return Ok(response);
+    }
+    if let Err(number_error) = check_number_unchanged(current, &target_eng) {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid number change",
+                "details": number_error
+            })));
+    }
+    if let Err(reference_error) = known.check(Some(current), &target_eng) {
+        return Ok(unknown_reference(reference_error));
+    }
+    if let Err(transition_error) = check_transition(current, &target_eng) {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid status change",
+                "details": transition_error
+            })));
+    }
+    target_eng.version = current.version + 1;
+
+    let conflicts = if reschedules(current, &target_eng) {
+        find_conflicts(repo.iter(), &target_eng)
+    } else {
+        Vec::new()
+    };
+    if !conflicts.is_empty() && conflict_config.mode == ConflictMode::Reject {
+        return Ok(conflict_response(&conflicts));
+    }
+
+    let entry = AuditEntry::new(Operation::Update, &actor, Some(current), Some(&target_eng));
+    audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .push(entry);
+
+    repo.replace(target_eng.clone());
+
+    let mut response = HttpResponse::Ok();
+    response.insert_header(("ETag", etag(target_eng.version).to_string()));
+    Ok(with_conflict_warning(response, &conflicts))
+}
+
+#[patch("/engs")]
+pub async fn edit_eng(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    body: Json<Engagement>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut repo_guard = repo.lock().map_err(|_| {
+        actix_web::error::ErrorInternalServerError("Failed to acquire repo lock (UPDATE)")
+    })?;
+    let known = KnownNames::load(&instructors, &hosts)?;
+
+    update_engagement(
+        &mut repo_guard,
+        &audit,
+        &known,
+        &conflict_config,
+        &req,
+        body.into_inner(),
+    )
+}
+
+#[patch("/engs/{id}")]
+#[allow(clippy::too_many_arguments)]
+pub async fn patch_eng(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    let patch = match parse_merge_patch(&req, &body) {
+        Ok(patch) => patch,
+        Err(response) => return Ok(response),
+    };
+
+    let mut repo_guard = repo.lock().map_err(|_| {
+        actix_web::error::ErrorInternalServerError("Failed to acquire repo lock (UPDATE)")
+    })?;
+    let known = KnownNames::load(&instructors, &hosts)?;
+
+    let Some(current) = repo_guard.iter().find(|e| e.id == target_id) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let mut merged =
+        serde_json::to_value(current).map_err(actix_web::error::ErrorInternalServerError)?;
+    merge_patch(&mut merged, &patch);
+
+    let mut edited: Engagement = match serde_json::from_value(merged) {
+        Ok(edited) => edited,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Validation failed",
+                    "details": e.to_string()
+                })))
         }
-        Err(_) => Err(actix_web::error::ErrorInternalServerError(
-            "Failed to acquire repo lock (UPDATE)",
-        )),
+    };
+    edited.id = target_id;
+    // The stored value carries the previous editor and date, so it is never reused
+    if patch.get("last_updated_by").is_none() {
+        edited.last_updated_by = Some(actor(&req, None));
     }
+
+    update_engagement(
+        &mut repo_guard,
+        &audit,
+        &known,
+        &conflict_config,
+        &req,
+        edited,
+    )
 }
 
 #[delete("/engs/{id}")]
 pub async fn delete_eng(
     repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
     path: Path<Uuid>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let target_id = path.into_inner();
+    let actor = actor(&req, None);
 
     let mut repo_guard = repo
         .lock()
         .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let mut audit_guard = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?;
+    let mut trash_guard = trash
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire trash lock"))?;
 
     let target_eng = repo_guard.iter().find(|e| e.id == target_id).cloned();
 
     if let Some(eng) = target_eng {
-        repo_guard.remove(&eng);
-
-        // If it has a number, process the decrements
-        if let Some(num) = eng.number {
-            let parsed_num = num
-                .parse::<usize>()
-                .map_err(|_| actix_web::error::ErrorInternalServerError("Invalid number format"))?;
-
-            // Create vector of engagements to update
-            let to_update: Vec<_> = repo_guard
-                .iter()
-                .filter(|e| {
-                    e.number
-                        .as_ref()
-                        .and_then(|n| n.parse::<usize>().ok())
-                        .map_or(false, |existing| existing > parsed_num)
-                })
-                .cloned()
-                .collect();
-
-            // Remove all affected engagements
-            for eng in &to_update {
-                repo_guard.remove(eng);
-            }
+        remove_numbered(&mut repo_guard, &mut audit_guard, &actor, &eng);
 
-            // Insert updated engagements
-            for mut update_eng in to_update {
-                if let Some(ref mut curr_num) = update_eng.number {
-                    if let Ok(existing_num) = curr_num.parse::<usize>() {
-                        *curr_num = (existing_num - 1).to_string();
-                    }
-                }
-                repo_guard.insert(update_eng);
-            }
-        }
+        let entry = TrashEntry::new(TrashedRecord::Engagement(eng), &actor);
+        let trash_id = entry.id;
+        trash_guard.push(entry);
 
-        Ok(HttpResponse::Ok().finish())
+        Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(json!({ "trash_id": trash_id })))
     } else {
         Ok(HttpResponse::NotFound().finish())
     }
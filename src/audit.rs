@@ -0,0 +1,124 @@
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::Engagement;
+use crate::types::AuditRepo;
+
+// Bookkeeping fields that change on every write and would only add noise to a diff
+const UNTRACKED_FIELDS: [&str; 2] = ["last_updated_by", "version"];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub engagement_id: Uuid,
+    pub actor: String,
+    pub timestamp: DateTime<Utc>,
+    pub operation: Operation,
+    pub changes: Vec<FieldChange>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        operation: Operation,
+        actor: &str,
+        before: Option<&Engagement>,
+        after: Option<&Engagement>,
+    ) -> Self {
+        Self {
+            engagement_id: after.or(before).map(|e| e.id).unwrap_or_default(),
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+            operation,
+            changes: diff(before, after),
+        }
+    }
+}
+
+// A missing side (create or delete) counts as an engagement with no fields set
+pub fn diff(before: Option<&Engagement>, after: Option<&Engagement>) -> Vec<FieldChange> {
+    let to_map = |eng: Option<&Engagement>| -> Map<String, Value> {
+        match eng.map(serde_json::to_value) {
+            Some(Ok(Value::Object(map))) => map,
+            _ => Map::new(),
+        }
+    };
+    let (before, after) = (to_map(before), to_map(after));
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| !UNTRACKED_FIELDS.contains(&field.as_str()))
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            old: before.get(field).cloned(),
+            new: after.get(field).cloned(),
+        })
+        .collect()
+}
+
+// The X-Updated-By header wins over the name in the body
+pub fn actor(req: &HttpRequest, fallback: Option<&str>) -> String {
+    req.headers()
+        .get("X-Updated-By")
+        .and_then(|value| value.to_str().ok())
+        .or(fallback)
+        .map(|name| ammonia::clean(name.trim()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[get("/engs/{id}/history")]
+pub async fn get_eng_history(
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+
+    let exists = engagements
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?
+        .iter()
+        .any(|e| e.id == target_id);
+
+    let history: Vec<AuditEntry> = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .iter()
+        .filter(|entry| entry.engagement_id == target_id)
+        .cloned()
+        .collect();
+
+    if !exists && history.is_empty() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(history))
+}
@@ -12,12 +12,18 @@ use thiserror::Error;
 use tokio::time::interval;
 
 use crate::api::Engagement;
+use crate::audit::AuditEntry;
+use crate::hosts::Host;
+use crate::instructors::Instructor;
+use crate::profiles::StoredProfile;
 use crate::translations::*;
+use crate::translators::Translator;
+use crate::trash::TrashEntry;
 
 #[derive(Debug, Error)]
 pub enum BackupError {
     #[error("AWS SDK error: {0}")]
-    AwsError(#[from] aws_sdk_s3::Error),
+    AwsError(#[from] Box<aws_sdk_s3::Error>),
 
     #[error("Compression error: {0}")]
     CompressionError(#[from] std::io::Error),
@@ -45,21 +51,27 @@ where
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
-struct BackupData {
-    engagements: HashSet<Engagement>,
-    instructors: HashSet<String>,
-    hosts: HashSet<String>,
-    translations: Vec<Translation>,
-    translators: HashSet<String>,
+pub struct BackupData {
+    pub engagements: HashSet<Engagement>,
+    pub instructors: HashSet<Instructor>,
+    pub hosts: HashSet<Host>,
+    pub translations: Vec<Translation>,
+    pub translators: HashSet<Translator>,
+    pub audit_log: Vec<AuditEntry>,
+    pub trash: Vec<TrashEntry>,
+    pub next_translation_id: u32,
 }
 
 #[derive(serde::Deserialize, Debug)]
 struct IntermediateBackupData {
     engagements: Option<HashSet<Engagement>>,
-    instructors: Option<HashSet<String>>,
-    hosts: Option<HashSet<String>>,
+    instructors: Option<Vec<StoredProfile<Instructor>>>,
+    hosts: Option<Vec<StoredProfile<Host>>>,
     translations: Option<Vec<Translation>>,
-    translators: Option<HashSet<String>>,
+    translators: Option<Vec<StoredProfile<Translator>>>,
+    audit_log: Option<Vec<AuditEntry>>,
+    trash: Option<Vec<TrashEntry>>,
+    next_translation_id: Option<u32>,
 }
 
 #[derive(Clone, Debug)]
@@ -101,6 +113,8 @@ pub struct BackupMetrics {
     pub host_count: usize,
     pub translations_count: usize,
     pub translators_count: usize,
+    pub audit_count: usize,
+    pub trash_count: usize,
     pub compressed_size: usize,
     pub compression_time_ms: u128,
     pub upload_time_ms: u128,
@@ -108,21 +122,28 @@ pub struct BackupMetrics {
 
 pub struct BackupSystem {
     engagements: Arc<Mutex<HashSet<Engagement>>>,
-    instructors: Arc<Mutex<HashSet<String>>>,
-    hosts: Arc<Mutex<HashSet<String>>>,
+    instructors: Arc<Mutex<HashSet<Instructor>>>,
+    hosts: Arc<Mutex<HashSet<Host>>>,
     translations: Arc<Mutex<Vec<Translation>>>,
-    translators: Arc<Mutex<HashSet<String>>>,
+    translators: Arc<Mutex<HashSet<Translator>>>,
+    audit_log: Arc<Mutex<Vec<AuditEntry>>>,
+    trash: Arc<Mutex<Vec<TrashEntry>>>,
+    translation_ids: Arc<Mutex<u32>>,
     config: BackupConfig,
     client: S3Client,
 }
 
 impl BackupSystem {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         engagements: Arc<Mutex<HashSet<Engagement>>>,
-        instructors: Arc<Mutex<HashSet<String>>>,
-        hosts: Arc<Mutex<HashSet<String>>>,
+        instructors: Arc<Mutex<HashSet<Instructor>>>,
+        hosts: Arc<Mutex<HashSet<Host>>>,
         translations: Arc<Mutex<Vec<Translation>>>,
-        translators: Arc<Mutex<HashSet<String>>>,
+        translators: Arc<Mutex<HashSet<Translator>>>,
+        audit_log: Arc<Mutex<Vec<AuditEntry>>>,
+        trash: Arc<Mutex<Vec<TrashEntry>>>,
+        translation_ids: Arc<Mutex<u32>>,
         config: BackupConfig,
     ) -> Result<Self, BackupError> {
         let region = Region::new(config.region.clone());
@@ -140,6 +161,9 @@ impl BackupSystem {
             hosts,
             translations,
             translators,
+            audit_log,
+            trash,
+            translation_ids,
             config,
             client,
         })
@@ -162,12 +186,14 @@ impl BackupSystem {
                 match self.perform_backup().await {
                     Ok(metrics) => {
                         log::info!(
-                            "Backup task completed for {} invitations, {} instructors, {} hosts, {} translations, and {} translators having compressed size {} bytes taking {} ms to compress, and uploaded in {} ms",
+                            "Backup task completed for {} invitations, {} instructors, {} hosts, {} translations, {} translators, {} audit entries, and {} trashed records having compressed size {} bytes taking {} ms to compress, and uploaded in {} ms",
                             metrics.eng_count,
                             metrics.instructor_count,
                             metrics.host_count,
                             metrics.translations_count,
                             metrics.translators_count,
+                            metrics.audit_count,
+                            metrics.trash_count,
                             metrics.compressed_size,
                             metrics.compression_time_ms,
                             metrics.upload_time_ms,
@@ -186,12 +212,24 @@ impl BackupSystem {
         let key = format!("{}/backup_{}.json.zst", self.config.prefix, timestamp);
 
         // Serialize engagements in a separate scope so the lock is dropped
-        let (json, eng_count, instructor_count, host_count, translations_count, translators_count) = {
+        let (
+            json,
+            eng_count,
+            instructor_count,
+            host_count,
+            translations_count,
+            translators_count,
+            audit_count,
+            trash_count,
+        ) = {
             let engagements = self.engagements.lock().unwrap();
+            let audit_log = self.audit_log.lock().unwrap();
             let instructors = self.instructors.lock().unwrap();
             let hosts = self.hosts.lock().unwrap();
             let translations = self.translations.lock().unwrap();
             let translators = self.translators.lock().unwrap();
+            let trash = self.trash.lock().unwrap();
+            let translation_ids = self.translation_ids.lock().unwrap();
 
             let backup_data = BackupData {
                 engagements: engagements.clone(),
@@ -199,6 +237,9 @@ impl BackupSystem {
                 hosts: hosts.clone(),
                 translations: translations.clone(),
                 translators: translators.clone(),
+                audit_log: audit_log.clone(),
+                trash: trash.clone(),
+                next_translation_id: *translation_ids,
             };
 
             let json = serde_json::to_string(&backup_data)?;
@@ -209,8 +250,10 @@ impl BackupSystem {
                 hosts.len(),
                 translations.len(),
                 translators.len(),
+                audit_log.len(),
+                trash.len(),
             )
-        }; // lock is dropped 
+        }; // lock is dropped
 
         let compression_start = std::time::Instant::now();
         let compressed =
@@ -232,6 +275,8 @@ impl BackupSystem {
             .metadata("host_count", host_count.to_string())
             .metadata("translations_count", translations_count.to_string())
             .metadata("host_count", translators_count.to_string())
+            .metadata("audit_count", audit_count.to_string())
+            .metadata("trash_count", trash_count.to_string())
             .send()
             .await
             .map_err(BackupError::from)?;
@@ -247,6 +292,8 @@ impl BackupSystem {
             host_count,
             translations_count,
             translators_count,
+            audit_count,
+            trash_count,
             compressed_size,
             compression_time_ms: compression_time.as_millis(),
             upload_time_ms: upload_time.as_millis(),
@@ -284,18 +331,7 @@ impl BackupSystem {
         Ok(())
     }
 
-    pub async fn restore_latest_backup(
-        &self,
-    ) -> Result<
-        (
-            HashSet<Engagement>,
-            HashSet<String>,
-            HashSet<String>,
-            Vec<Translation>,
-            HashSet<String>,
-        ),
-        Box<dyn std::error::Error>,
-    > {
+    pub async fn restore_latest_backup(&self) -> Result<BackupData, Box<dyn std::error::Error>> {
         let objects = self
             .client
             .list_objects_v2()
@@ -331,26 +367,42 @@ impl BackupSystem {
         let intermediate: IntermediateBackupData = serde_json::from_str(json_str)?;
         let backup_data = BackupData {
             engagements: intermediate.engagements.unwrap_or_default(),
-            instructors: intermediate.instructors.unwrap_or_default(),
-            hosts: intermediate.hosts.unwrap_or_default(),
+            instructors: intermediate
+                .instructors
+                .unwrap_or_default()
+                .into_iter()
+                .map(StoredProfile::into_profile)
+                .collect(),
+            hosts: intermediate
+                .hosts
+                .unwrap_or_default()
+                .into_iter()
+                .map(StoredProfile::into_profile)
+                .collect(),
             translations: intermediate.translations.unwrap_or_default(),
-            translators: intermediate.translators.unwrap_or_default(),
+            translators: intermediate
+                .translators
+                .unwrap_or_default()
+                .into_iter()
+                .map(StoredProfile::into_profile)
+                .collect(),
+            audit_log: intermediate.audit_log.unwrap_or_default(),
+            trash: intermediate.trash.unwrap_or_default(),
+            // Older backups predate the allocator; the startup repair moves it
+            // past every stored id
+            next_translation_id: intermediate.next_translation_id.unwrap_or(1),
         };
         log::info!(
-            "Loading {} engagements, {} instructors, {} hosts, {} translations, and {} translators from backup",
+            "Loading {} engagements, {} instructors, {} hosts, {} translations, {} translators, {} audit entries, and {} trashed records from backup",
             backup_data.engagements.len(),
             backup_data.instructors.len(),
             backup_data.hosts.len(),
             backup_data.translations.len(),
             backup_data.translators.len(),
+            backup_data.audit_log.len(),
+            backup_data.trash.len(),
         );
 
-        Ok((
-            backup_data.engagements,
-            backup_data.instructors,
-            backup_data.hosts,
-            backup_data.translations,
-            backup_data.translators,
-        ))
+        Ok(backup_data)
     }
 }
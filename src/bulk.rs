@@ -0,0 +1,325 @@
+use actix_web::{
+    post,
+    web::{Data, Json},
+    HttpRequest, HttpResponse,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{Engagement, EngagementQuery, NewEngagement};
+use crate::audit::{actor, AuditEntry, Operation};
+use crate::conflicts::{find_conflicts, reschedules, ConflictConfig, ConflictMode};
+use crate::integrity::KnownNames;
+use crate::merge_patch::merge_patch;
+use crate::numbering::{check_number_unchanged, insert_numbered, remove_numbered};
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{AuditRepo, HostRepo, InstructorRepo, TrashRepo};
+use crate::workflow::check_transition;
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BulkOperation {
+    Create {
+        engagement: Box<NewEngagement>,
+    },
+    Update {
+        id: Uuid,
+        changes: Value,
+        #[serde(default)]
+        version: Option<u64>,
+    },
+    Delete {
+        id: Uuid,
+    },
+}
+
+// Filter and set is not version checked; send update operations with
+// versions to guard against lost updates
+#[derive(serde::Deserialize, Debug)]
+pub struct BulkRequest {
+    #[serde(default)]
+    pub operations: Vec<BulkOperation>,
+    #[serde(default)]
+    pub filter: Option<EngagementQuery>,
+    #[serde(default)]
+    pub set: Option<Value>,
+    pub last_updated_by: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+struct BulkResult {
+    index: usize,
+    op: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Uuid>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    conflicts: Vec<Uuid>,
+}
+
+impl BulkResult {
+    fn new(
+        index: usize,
+        op: &'static str,
+        id: Option<Uuid>,
+        outcome: Result<Vec<Uuid>, String>,
+    ) -> Self {
+        match outcome {
+            Ok(conflicts) => Self {
+                index,
+                op,
+                id,
+                ok: true,
+                error: None,
+                conflicts,
+            },
+            Err(error) => Self {
+                index,
+                op,
+                id,
+                ok: false,
+                error: Some(error),
+                conflicts: Vec::new(),
+            },
+        }
+    }
+}
+
+// Working copies, committed only if every item succeeds
+struct Batch<'a> {
+    repo: HashSet<Engagement>,
+    audit: Vec<AuditEntry>,
+    trash: Vec<TrashEntry>,
+    actor: &'a str,
+    last_updated_by: String,
+    known: KnownNames,
+    conflict_mode: &'a ConflictMode,
+}
+
+impl Batch<'_> {
+    fn check_conflicts(&self, eng: &Engagement) -> Result<Vec<Uuid>, String> {
+        let conflicts = find_conflicts(self.repo.iter(), eng);
+        if !conflicts.is_empty() && *self.conflict_mode == ConflictMode::Reject {
+            Err(format!("Scheduling conflict with {:?}", conflicts))
+        } else {
+            Ok(conflicts)
+        }
+    }
+
+    fn create(&mut self, new_eng: &NewEngagement) -> Result<(Uuid, Vec<Uuid>), String> {
+        new_eng.validate()?;
+        let eng = new_eng.to_engagement();
+        self.known.check(None, &eng)?;
+        let conflicts = self.check_conflicts(&eng)?;
+        let id = eng.id;
+        insert_numbered(&mut self.repo, &mut self.audit, self.actor, eng);
+        Ok((id, conflicts))
+    }
+
+    fn update(
+        &mut self,
+        id: Uuid,
+        changes: &Value,
+        version: Option<u64>,
+    ) -> Result<Vec<Uuid>, String> {
+        if !changes.is_object() {
+            return Err("Changes must be a JSON object".to_string());
+        }
+        let Some(current) = self.repo.iter().find(|e| e.id == id).cloned() else {
+            return Err("Engagement not found".to_string());
+        };
+        if version.is_some_and(|version| version != current.version) {
+            return Err(format!(
+                "Version mismatch: engagement is at version {}",
+                current.version
+            ));
+        }
+
+        let mut merged = serde_json::to_value(&current).map_err(|e| e.to_string())?;
+        merge_patch(&mut merged, changes);
+        let edited: Engagement = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+        edited.validate()?;
+
+        let mut eng = edited.clean();
+        eng.id = id;
+        eng.last_updated_by = Some(self.last_updated_by.clone());
+        check_number_unchanged(&current, &eng)?;
+        check_transition(&current, &eng)?;
+        self.known.check(Some(&current), &eng)?;
+        eng.version = current.version + 1;
+
+        let conflicts = if reschedules(&current, &eng) {
+            self.check_conflicts(&eng)?
+        } else {
+            Vec::new()
+        };
+        self.audit.push(AuditEntry::new(
+            Operation::Update,
+            self.actor,
+            Some(&current),
+            Some(&eng),
+        ));
+        self.repo.replace(eng);
+        Ok(conflicts)
+    }
+
+    fn delete(&mut self, id: Uuid) -> Result<(), String> {
+        let Some(eng) = self.repo.iter().find(|e| e.id == id).cloned() else {
+            return Err("Engagement not found".to_string());
+        };
+        remove_numbered(&mut self.repo, &mut self.audit, self.actor, &eng);
+        self.trash
+            .push(TrashEntry::new(TrashedRecord::Engagement(eng), self.actor));
+        Ok(())
+    }
+}
+
+fn invalid_bulk(details: &str) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Invalid bulk request",
+            "details": details
+        }))
+}
+
+// Items run in order against a working copy, so later items see earlier ones
+#[post("/engs/bulk")]
+#[allow(clippy::too_many_arguments)]
+pub async fn bulk_engs(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    body: Json<BulkRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let body = body.into_inner();
+    if let Some(Err(details)) = body.filter.as_ref().map(EngagementQuery::validate) {
+        return Ok(invalid_bulk(&details));
+    }
+    let assignment = match (&body.filter, &body.set) {
+        (None, None) if body.operations.is_empty() => {
+            return Ok(invalid_bulk("Send either operations or a filter and set"))
+        }
+        (None, None) => None,
+        (Some(_), Some(_)) if !body.operations.is_empty() => {
+            return Ok(invalid_bulk(
+                "Operations cannot be combined with a filter and set",
+            ))
+        }
+        (Some(filter), _) if *filter == EngagementQuery::default() => {
+            return Ok(invalid_bulk("The filter must restrict at least one field"))
+        }
+        (Some(filter), Some(set)) if set.is_object() => Some((filter, set)),
+        (Some(_), Some(_)) => return Ok(invalid_bulk("Set must be a JSON object")),
+        _ => return Ok(invalid_bulk("A filter and set must be sent together")),
+    };
+
+    let actor = actor(&req, Some(&body.last_updated_by));
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let mut batch = Batch {
+        repo: repo_guard.clone(),
+        audit: Vec::new(),
+        trash: Vec::new(),
+        actor: &actor,
+        last_updated_by: format!(
+            "{} {}",
+            body.last_updated_by,
+            chrono::Utc::now().format("%Y-%m-%d")
+        ),
+        known: KnownNames::load(&instructors, &hosts)?,
+        conflict_mode: &conflict_config.mode,
+    };
+    let mut results = Vec::new();
+
+    match assignment {
+        Some((filter, set)) => {
+            let mut targets: Vec<&Engagement> =
+                repo_guard.iter().filter(|e| filter.matches(e)).collect();
+            targets.sort_by(|a, b| a.number.cmp(&b.number).then_with(|| a.id.cmp(&b.id)));
+
+            for (index, eng) in targets.into_iter().enumerate() {
+                let outcome = batch.update(eng.id, set, None);
+                results.push(BulkResult::new(index, "update", Some(eng.id), outcome));
+            }
+        }
+        None => {
+            for (index, operation) in body.operations.iter().enumerate() {
+                let result = match operation {
+                    BulkOperation::Create { engagement } => match batch.create(engagement) {
+                        Ok((id, conflicts)) => {
+                            BulkResult::new(index, "create", Some(id), Ok(conflicts))
+                        }
+                        Err(error) => BulkResult::new(index, "create", None, Err(error)),
+                    },
+                    BulkOperation::Update {
+                        id,
+                        changes,
+                        version,
+                    } => {
+                        let outcome = match version {
+                            Some(_) => batch.update(*id, changes, *version),
+                            None => Err(
+                                "A version is required; send the engagement's current version"
+                                    .to_string(),
+                            ),
+                        };
+                        BulkResult::new(index, "update", Some(*id), outcome)
+                    }
+                    BulkOperation::Delete { id } => {
+                        let outcome = batch.delete(*id).map(|_| Vec::new());
+                        BulkResult::new(index, "delete", Some(*id), outcome)
+                    }
+                };
+                results.push(result);
+            }
+        }
+    }
+
+    if results.iter().any(|result| !result.ok) {
+        return Ok(HttpResponse::UnprocessableEntity()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Bulk operation failed",
+                "details": "No changes were applied",
+                "results": results
+            })));
+    }
+
+    let Batch {
+        repo: working_repo,
+        audit: working_audit,
+        trash: working_trash,
+        ..
+    } = batch;
+
+    audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .extend(working_audit);
+    trash
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire trash lock"))?
+        .extend(working_trash);
+    *repo_guard = working_repo;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(json!({
+            "applied": results.len(),
+            "results": results
+        })))
+}
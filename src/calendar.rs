@@ -0,0 +1,182 @@
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpResponse,
+};
+use chrono::{NaiveDate, Utc};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::api::{Engagement, Status};
+use crate::names::normalize_name;
+
+const PRODID: &str = "-//Koradi//Admin//EN";
+const UID_DOMAIN: &str = "koradi-admin";
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+// RFC 5545 section 3.3.11
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// At most 75 octets per line, never splitting a UTF-8 character
+fn fold_line(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn ics_status(status: &Status) -> &'static str {
+    match status {
+        Status::Planning | Status::Invited => "TENTATIVE",
+        Status::Confirmed | Status::Complete => "CONFIRMED",
+        Status::Rejected => "CANCELLED",
+    }
+}
+
+fn render_event(eng: &Engagement, dtstamp: &str, out: &mut String) {
+    let Ok(date) = NaiveDate::parse_from_str(&eng.date, "%Y-%m-%d") else {
+        log::warn!(
+            "Skipping engagement {} with invalid date in calendar",
+            eng.id
+        );
+        return;
+    };
+
+    let summary = if eng.num_parts > 1 {
+        format!("{} (part {} of {})", eng.title, eng.part, eng.num_parts)
+    } else {
+        eng.title.clone()
+    };
+
+    let mut description = format!(
+        "Instructor: {}\nHost: {}\nLanguage: {:?}\nStatus: {:?}",
+        eng.instructor, eng.host, eng.language, eng.status
+    );
+    if let Some(notes) = eng.notes.as_ref().filter(|n| !n.is_empty()) {
+        description.push_str(&format!("\n\n{}", notes));
+    }
+
+    // Timed engagements are written in UTC, everything else as an all-day event
+    let (dtstart, dtend) = match eng.starts_at() {
+        Some(start) => (
+            format!("DTSTART:{}", start.format(UTC_FORMAT)),
+            eng.ends_at()
+                .map(|end| format!("DTEND:{}", end.format(UTC_FORMAT))),
+        ),
+        None => (
+            format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+            Some(format!(
+                "DTEND;VALUE=DATE:{}",
+                (date + chrono::Duration::days(1)).format("%Y%m%d")
+            )),
+        ),
+    };
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}@{}", eng.id, UID_DOMAIN),
+        format!("DTSTAMP:{}", dtstamp),
+        dtstart,
+    ];
+    lines.extend(dtend);
+    lines.extend([
+        format!("SUMMARY:{}", escape_text(&summary)),
+        format!("LOCATION:{}", escape_text(&eng.host)),
+        format!("DESCRIPTION:{}", escape_text(&description)),
+        format!("STATUS:{}", ics_status(&eng.status)),
+        format!("SEQUENCE:{}", eng.version),
+        "END:VEVENT".to_string(),
+    ]);
+    for line in lines {
+        fold_line(&line, out);
+    }
+}
+
+// UIDs come from the engagement id so subscribed calendars update in place
+pub fn render_calendar<'a>(
+    name: &str,
+    engagements: impl IntoIterator<Item = &'a Engagement>,
+) -> String {
+    let mut engagements: Vec<&Engagement> = engagements.into_iter().collect();
+    engagements.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+
+    let dtstamp = Utc::now().format(UTC_FORMAT).to_string();
+    let mut out = String::new();
+
+    for line in [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ] {
+        fold_line(&line, &mut out);
+    }
+    for eng in engagements {
+        render_event(eng, &dtstamp, &mut out);
+    }
+    fold_line("END:VCALENDAR", &mut out);
+
+    out
+}
+
+fn calendar_response<F>(
+    repo: &Mutex<HashSet<Engagement>>,
+    name: &str,
+    filter: F,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    F: Fn(&Engagement) -> bool,
+{
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(render_calendar(
+            name,
+            repo_guard.iter().filter(|e| filter(e)),
+        )))
+}
+
+#[get("/calendar/instructors/{name}.ics")]
+pub async fn get_instructor_calendar(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    name: Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let sanitized = normalize_name(&name);
+    calendar_response(&repo, &sanitized, |e| e.instructor == sanitized)
+}
+
+#[get("/calendar/hosts/{name}.ics")]
+pub async fn get_host_calendar(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    name: Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let sanitized = normalize_name(&name);
+    calendar_response(&repo, &sanitized, |e| e.host == sanitized)
+}
+
+#[get("/calendar/all.ics")]
+pub async fn get_full_calendar(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    calendar_response(&repo, "All engagements", |_| true)
+}
@@ -0,0 +1,151 @@
+use actix_web::{get, web::Data, HttpResponse, HttpResponseBuilder};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{Engagement, Status};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConflictMode {
+    Warn,
+    Reject,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConflictConfig {
+    pub mode: ConflictMode,
+}
+
+impl ConflictConfig {
+    pub fn from_env() -> Self {
+        let mode = match std::env::var("CONFLICT_MODE")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "reject" => ConflictMode::Reject,
+            _ => ConflictMode::Warn,
+        };
+
+        Self { mode }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub enum ConflictKind {
+    Instructor,
+    Host,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub name: String,
+    pub date: String,
+    pub engagements: Vec<Uuid>,
+}
+
+pub fn find_conflicts<'a>(
+    engagements: impl IntoIterator<Item = &'a Engagement>,
+    candidate: &Engagement,
+) -> Vec<Uuid> {
+    if candidate.status == Status::Rejected {
+        return Vec::new();
+    }
+
+    let mut ids: Vec<Uuid> = engagements
+        .into_iter()
+        .filter(|e| e.id != candidate.id && e.status != Status::Rejected)
+        .filter(|e| e.date == candidate.date)
+        .filter(|e| e.instructor == candidate.instructor || e.host == candidate.host)
+        .map(|e| e.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+// Other edits skip the check, so engagements that were already
+// double-booked stay editable
+pub fn reschedules(before: &Engagement, after: &Engagement) -> bool {
+    before.date != after.date
+        || before.instructor != after.instructor
+        || before.host != after.host
+        || before.status != after.status
+}
+
+pub fn conflict_response(conflicts: &[Uuid]) -> HttpResponse {
+    HttpResponse::Conflict()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Scheduling conflict",
+            "details": "The instructor or host already has an engagement on this date",
+            "conflicts": conflicts
+        }))
+}
+
+pub fn with_conflict_warning(mut builder: HttpResponseBuilder, conflicts: &[Uuid]) -> HttpResponse {
+    if conflicts.is_empty() {
+        builder.finish()
+    } else {
+        builder.content_type("application/json").json(json!({
+            "warning": "Scheduling conflict",
+            "conflicts": conflicts
+        }))
+    }
+}
+
+pub fn all_conflicts<'a>(engagements: impl IntoIterator<Item = &'a Engagement>) -> Vec<Conflict> {
+    let mut by_instructor: BTreeMap<(String, String), Vec<Uuid>> = BTreeMap::new();
+    let mut by_host: BTreeMap<(String, String), Vec<Uuid>> = BTreeMap::new();
+
+    for eng in engagements
+        .into_iter()
+        .filter(|e| e.status != Status::Rejected)
+    {
+        by_instructor
+            .entry((eng.date.clone(), eng.instructor.clone()))
+            .or_default()
+            .push(eng.id);
+        by_host
+            .entry((eng.date.clone(), eng.host.clone()))
+            .or_default()
+            .push(eng.id);
+    }
+
+    let to_conflicts = |groups: BTreeMap<(String, String), Vec<Uuid>>, kind: ConflictKind| {
+        groups
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(move |((date, name), mut ids)| {
+                ids.sort();
+                Conflict {
+                    kind: kind.clone(),
+                    name,
+                    date,
+                    engagements: ids,
+                }
+            })
+    };
+
+    let mut conflicts: Vec<Conflict> = to_conflicts(by_instructor, ConflictKind::Instructor)
+        .chain(to_conflicts(by_host, ConflictKind::Host))
+        .collect();
+    conflicts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+    conflicts
+}
+
+#[get("/engs/conflicts")]
+pub async fn get_conflicts(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(all_conflicts(repo_guard.iter())))
+}
@@ -0,0 +1,45 @@
+// Deliberately loose; deliverability is not our concern
+pub fn validate_email(email: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid email address: {}", email);
+
+    if email.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+    if local.is_empty()
+        || domain.contains('@')
+        || domain.starts_with('.')
+        || domain.ends_with('.')
+        || !domain.contains('.')
+    {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+// E.164 allows at most 15 digits; fewer than 7 is never a reachable number
+pub fn validate_phone(phone: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid phone number: {}", phone);
+
+    let rest = phone.strip_prefix('+').unwrap_or(phone);
+    if !rest
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '.' | '(' | ')'))
+    {
+        return Err(invalid());
+    }
+
+    let digits = rest.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+pub fn clean_optional(value: Option<&str>) -> Option<String> {
+    value
+        .map(|value| ammonia::clean(value.trim()))
+        .filter(|value| !value.is_empty())
+}
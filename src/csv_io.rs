@@ -0,0 +1,320 @@
+use actix_web::{
+    get, post,
+    web::{Bytes, Data, Json, Query},
+    HttpRequest, HttpResponse,
+};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{
+    invalid_query, resolve_engagement_query, Engagement, EngagementQuery, ListOptions,
+    NewEngagement,
+};
+use crate::audit::actor;
+use crate::conflicts::{find_conflicts, ConflictConfig, ConflictMode};
+use crate::integrity::KnownNames;
+use crate::numbering::insert_numbered;
+use crate::types::{AuditRepo, HostRepo, InstructorRepo};
+
+const EXPORT_COLUMNS: [&str; 20] = [
+    "id",
+    "number",
+    "date",
+    "start_time",
+    "end_time",
+    "timezone",
+    "instructor",
+    "host",
+    "language",
+    "title",
+    "part",
+    "num_parts",
+    "series_id",
+    "status",
+    "host_status",
+    "flyer_status",
+    "activity_type",
+    "notes",
+    "last_updated_by",
+    "version",
+];
+
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+// Neutralizes cells a spreadsheet would otherwise evaluate as a formula
+fn escape_cell(value: &str) -> String {
+    if value.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+const ESCAPED_COLUMNS: [&str; 6] = [
+    "instructor",
+    "host",
+    "title",
+    "activity_type",
+    "notes",
+    "last_updated_by",
+];
+
+// Reverses escape_cell, so an exported file imports unchanged
+fn unescape_cell(value: &str) -> &str {
+    match value.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_PREFIXES) => rest,
+        _ => value,
+    }
+}
+
+fn unescape_record(record: &csv::StringRecord, headers: &csv::StringRecord) -> csv::StringRecord {
+    record
+        .iter()
+        .zip(headers.iter())
+        .map(|(value, header)| {
+            if ESCAPED_COLUMNS.contains(&header) {
+                unescape_cell(value)
+            } else {
+                value
+            }
+        })
+        .collect()
+}
+
+fn export_row(eng: &Engagement) -> Vec<String> {
+    vec![
+        eng.id.to_string(),
+        eng.number.map(|n| n.to_string()).unwrap_or_default(),
+        eng.date.clone(),
+        eng.start_time.clone().unwrap_or_default(),
+        eng.end_time.clone().unwrap_or_default(),
+        eng.timezone.clone().unwrap_or_default(),
+        escape_cell(&eng.instructor),
+        escape_cell(&eng.host),
+        format!("{:?}", eng.language),
+        escape_cell(&eng.title),
+        eng.part.to_string(),
+        eng.num_parts.to_string(),
+        eng.series_id.map(|id| id.to_string()).unwrap_or_default(),
+        format!("{:?}", eng.status),
+        eng.host_status
+            .as_ref()
+            .map(|s| format!("{:?}", s))
+            .unwrap_or_default(),
+        eng.flyer_status
+            .as_ref()
+            .map(|s| format!("{:?}", s))
+            .unwrap_or_default(),
+        escape_cell(eng.activity_type.as_deref().unwrap_or_default()),
+        escape_cell(eng.notes.as_deref().unwrap_or_default()),
+        escape_cell(eng.last_updated_by.as_deref().unwrap_or_default()),
+        eng.version.to_string(),
+    ]
+}
+
+#[get("/engs/export.csv")]
+pub async fn export_engs_csv(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    req: HttpRequest,
+    body: Option<Json<EngagementQuery>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let options = match ListOptions::from_query_string(req.query_string()) {
+        Ok(options) => options,
+        Err(e) => return Ok(invalid_query(e)),
+    };
+    let query = match resolve_engagement_query(&req, body) {
+        Ok(query) => query,
+        Err(response) => return Ok(response),
+    };
+
+    let mut engagements: Vec<Engagement> = {
+        let repo_guard = repo.lock().map_err(|_| {
+            actix_web::error::ErrorInternalServerError("Failed to acquire repo lock")
+        })?;
+        repo_guard
+            .iter()
+            .filter(|x| query.matches(x))
+            .cloned()
+            .collect()
+    };
+    options.sort(&mut engagements);
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(EXPORT_COLUMNS)
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    for eng in &engagements {
+        writer
+            .write_record(export_row(eng))
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+    }
+    let csv = writer
+        .into_inner()
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            "attachment; filename=\"engagements.csv\"",
+        ))
+        .body(csv))
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(serde::Serialize, Debug)]
+struct RowError {
+    line: u64,
+    error: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+struct RowConflict {
+    line: u64,
+    conflicts: Vec<Uuid>,
+}
+
+// Rows apply in order to a copy of the repository, which replaces it only if
+// every row succeeds and this is not a dry run
+#[post("/engs/import")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_engs_csv(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    options: Query<ImportOptions>,
+    body: Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_ref());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Invalid CSV",
+                    "details": e.to_string()
+                })))
+        }
+    };
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let known = KnownNames::load(&instructors, &hosts)?;
+
+    let mut working_repo = repo_guard.clone();
+    let mut working_audit = Vec::new();
+    let mut errors = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut rows = 0;
+
+    for (index, record) in reader.records().enumerate() {
+        rows += 1;
+        // Line 1 is the header
+        let fallback_line = index as u64 + 2;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(RowError {
+                    line: e.position().map_or(fallback_line, |p| p.line()),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(fallback_line, |p| p.line());
+        let record = unescape_record(&record, &headers);
+
+        let new_eng = match record
+            .deserialize::<NewEngagement>(Some(&headers))
+            .map_err(|e| e.to_string())
+            .and_then(|new_eng| new_eng.validate().map(|_| new_eng))
+            .and_then(|new_eng| known.check(None, &new_eng.to_engagement()).map(|_| new_eng))
+        {
+            Ok(new_eng) => new_eng,
+            Err(error) => {
+                errors.push(RowError { line, error });
+                continue;
+            }
+        };
+
+        let eng = new_eng.to_engagement();
+        let clashes = find_conflicts(working_repo.iter(), &eng);
+        if !clashes.is_empty() {
+            if conflict_config.mode == ConflictMode::Reject {
+                errors.push(RowError {
+                    line,
+                    error: format!("Scheduling conflict with {:?}", clashes),
+                });
+                continue;
+            }
+            conflicts.push(RowConflict {
+                line,
+                conflicts: clashes,
+            });
+        }
+
+        let actor = actor(&req, Some(&new_eng.last_updated_by));
+        insert_numbered(&mut working_repo, &mut working_audit, &actor, eng);
+    }
+
+    if options.dry_run {
+        return Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(json!({
+                "dry_run": true,
+                "rows": rows,
+                "valid": errors.is_empty(),
+                "errors": errors,
+                "conflicts": conflicts
+            })));
+    }
+
+    if rows == 0 {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid CSV",
+                "details": "No rows to import"
+            })));
+    }
+
+    if !errors.is_empty() {
+        return Ok(HttpResponse::UnprocessableEntity()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Import failed",
+                "details": "No rows were imported",
+                "errors": errors
+            })));
+    }
+
+    audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .extend(working_audit);
+    *repo_guard = working_repo;
+
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .json(json!({
+            "imported": rows,
+            "conflicts": conflicts
+        })))
+}
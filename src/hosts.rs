@@ -1,23 +1,195 @@
 use actix_web::{
-    delete, get, post,
-    web::{Data, Path},
-    HttpResponse,
+    delete, get, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
 };
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{parse_timezone, Engagement};
+use crate::contact::{clean_optional, validate_email, validate_phone};
+use crate::integrity::{DeleteOptions, Role};
+use crate::names::{normalize_name, search, SearchQuery};
+use crate::preconditions::versioned_response;
+use crate::profiles::{
+    add_profile_by_name, create_profile, delete_profile, merge_profiles, rename_profile,
+    update_profile, validation_failed, EngagementRefs, MergeRequest, Profile, RenameRequest,
+};
+use crate::types::{AuditRepo, HostRepo, TrashRepo};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Host {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_person: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<u32>,
+    // IANA name, e.g. Europe/Madrid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl Profile for Host {
+    const KIND: &'static str = "host";
+    const REFERRERS: &'static str = "engagements";
+
+    fn from_name(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            address: None,
+            contact_person: None,
+            email: None,
+            phone: None,
+            capacity: None,
+            timezone: None,
+            notes: None,
+            version: 1,
+        }
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    fn absorb(&mut self, other: &Host) {
+        self.address = self.address.take().or_else(|| other.address.clone());
+        self.contact_person = self
+            .contact_person
+            .take()
+            .or_else(|| other.contact_person.clone());
+        self.email = self.email.take().or_else(|| other.email.clone());
+        self.phone = self.phone.take().or_else(|| other.phone.clone());
+        self.capacity = self.capacity.or(other.capacity);
+        self.timezone = self.timezone.take().or_else(|| other.timezone.clone());
+        self.notes = self.notes.take().or_else(|| other.notes.clone());
+    }
+}
+
+impl std::hash::Hash for Host {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
 
-use crate::types::HostRepo;
+impl PartialEq for Host {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Host {}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct HostInput {
+    pub name: String,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub contact_person: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub capacity: Option<u32>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl HostInput {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name must not be empty".to_string());
+        }
+        if let Some(email) = self.email.as_deref().filter(|e| !e.trim().is_empty()) {
+            validate_email(email.trim())?;
+        }
+        if let Some(phone) = self.phone.as_deref().filter(|p| !p.trim().is_empty()) {
+            validate_phone(phone.trim())?;
+        }
+        if self.capacity == Some(0) {
+            return Err("Capacity must be greater than 0".to_string());
+        }
+        if let Some(timezone) = self.timezone.as_deref().filter(|t| !t.trim().is_empty()) {
+            parse_timezone(timezone.trim())?;
+        }
+
+        Ok(())
+    }
+
+    fn to_host(&self, id: Uuid, version: u64) -> Host {
+        Host {
+            id,
+            name: normalize_name(&self.name),
+            address: clean_optional(self.address.as_deref()),
+            contact_person: clean_optional(self.contact_person.as_deref()),
+            email: clean_optional(self.email.as_deref()),
+            phone: clean_optional(self.phone.as_deref()),
+            capacity: self.capacity,
+            timezone: clean_optional(self.timezone.as_deref()),
+            notes: clean_optional(self.notes.as_deref()),
+            version,
+        }
+    }
+}
+
+#[post("/hosts")]
+pub async fn create_host(
+    repo: Data<HostRepo>,
+    body: Json<HostInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+    let host = body.to_host(Uuid::new_v4(), 1);
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(create_profile(&mut repo_guard, host))
+}
 
 #[post("/hosts/{new}")]
 pub async fn add_host(
     repo: Data<HostRepo>,
     new: Path<String>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&new);
-
     match repo.lock() {
-        Ok(mut repo) => {
-            repo.insert(sanitized);
-            Ok(HttpResponse::Created().finish())
-        }
+        Ok(mut repo) => Ok(add_profile_by_name(&mut repo, &new)),
         Err(_) => Err(actix_web::error::ErrorInternalServerError(
             "Failed to acquire repo lock",
         )),
@@ -25,11 +197,13 @@ pub async fn add_host(
 }
 
 #[get("/hosts")]
-pub async fn get_hosts(repo: Data<HostRepo>) -> Result<HttpResponse, actix_web::Error> {
+pub async fn get_hosts(
+    repo: Data<HostRepo>,
+    query: Query<SearchQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
     match repo.lock() {
         Ok(repo) => {
-            let mut hosts: Vec<String> = repo.iter().cloned().collect();
-            hosts.sort();
+            let hosts = search(repo.iter().cloned().collect(), |h| &h.name, &query);
 
             Ok(HttpResponse::Ok()
                 .content_type("application/json; charset=utf-8")
@@ -41,24 +215,109 @@ pub async fn get_hosts(repo: Data<HostRepo>) -> Result<HttpResponse, actix_web::
     }
 }
 
+#[get("/hosts/{id}")]
+pub async fn get_host(
+    repo: Data<HostRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    match repo_guard.iter().find(|h| h.id == *path) {
+        Some(host) => Ok(versioned_response(&req, host, host.version)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[patch("/hosts/{id}")]
+pub async fn update_host(
+    repo: Data<HostRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Json<HostInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Host)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(update_profile(
+        &mut repo_guard,
+        &req,
+        target_id,
+        |version| body.to_host(target_id, version),
+        |from, to| refs.reassign(from, to),
+    ))
+}
+
 #[delete("/hosts/{h}")]
 pub async fn delete_host(
     repo: Data<HostRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
     h: Path<String>,
+    options: Query<DeleteOptions>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&h);
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Host)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
 
-    match repo.lock() {
-        Ok(mut repo) => {
-            if repo.contains(&sanitized) {
-                repo.remove(&sanitized);
-                Ok(HttpResponse::Ok().finish())
-            } else {
-                Ok(HttpResponse::NotFound().finish())
-            }
-        }
-        Err(_) => Err(actix_web::error::ErrorInternalServerError(
-            "Failed to acquire repo lock",
-        )),
-    }
+    Ok(delete_profile(
+        &mut repo_guard,
+        &mut refs,
+        &h,
+        options.reassign_to.as_deref(),
+    ))
+}
+
+#[post("/hosts/{h}/rename")]
+pub async fn rename_host(
+    repo: Data<HostRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    h: Path<String>,
+    body: Json<RenameRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Host)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(rename_profile(&mut repo_guard, &h, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
+}
+
+#[post("/hosts/merge")]
+pub async fn merge_hosts(
+    repo: Data<HostRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    body: Json<MergeRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Host)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(merge_profiles(&mut repo_guard, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
 }
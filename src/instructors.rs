@@ -1,22 +1,174 @@
-use crate::types::InstructorRepo;
+use crate::api::{Engagement, Language};
+use crate::contact::{clean_optional, validate_email, validate_phone};
+use crate::integrity::{DeleteOptions, Role};
+use crate::names::{normalize_name, search, SearchQuery};
+use crate::preconditions::versioned_response;
+use crate::profiles::{
+    add_profile_by_name, create_profile, delete_profile, merge_profiles, rename_profile,
+    update_profile, validation_failed, EngagementRefs, MergeRequest, Profile, RenameRequest,
+};
+use crate::types::{AuditRepo, InstructorRepo, TrashRepo};
 use actix_web::{
-    delete, get, post,
-    web::{Data, Path},
-    HttpResponse,
+    delete, get, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
 };
+use uuid::Uuid;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Instructor {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub languages: Vec<Language>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl Profile for Instructor {
+    const KIND: &'static str = "instructor";
+    const REFERRERS: &'static str = "engagements";
+
+    fn from_name(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            email: None,
+            phone: None,
+            languages: Vec::new(),
+            notes: None,
+            version: 1,
+        }
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    fn absorb(&mut self, other: &Instructor) {
+        self.email = self.email.take().or_else(|| other.email.clone());
+        self.phone = self.phone.take().or_else(|| other.phone.clone());
+        self.notes = self.notes.take().or_else(|| other.notes.clone());
+        for language in &other.languages {
+            if !self.languages.contains(language) {
+                self.languages.push(language.clone());
+            }
+        }
+    }
+}
+
+impl std::hash::Hash for Instructor {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialEq for Instructor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Instructor {}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct InstructorInput {
+    pub name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub languages: Vec<Language>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl InstructorInput {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name must not be empty".to_string());
+        }
+        if let Some(email) = self.email.as_deref().filter(|e| !e.trim().is_empty()) {
+            validate_email(email.trim())?;
+        }
+        if let Some(phone) = self.phone.as_deref().filter(|p| !p.trim().is_empty()) {
+            validate_phone(phone.trim())?;
+        }
+
+        Ok(())
+    }
+
+    fn to_instructor(&self, id: Uuid, version: u64) -> Instructor {
+        let mut languages: Vec<Language> = Vec::new();
+        for language in &self.languages {
+            if !languages.contains(language) {
+                languages.push(language.clone());
+            }
+        }
+
+        Instructor {
+            id,
+            name: normalize_name(&self.name),
+            email: clean_optional(self.email.as_deref()),
+            phone: clean_optional(self.phone.as_deref()),
+            languages,
+            notes: clean_optional(self.notes.as_deref()),
+            version,
+        }
+    }
+}
+
+#[post("/instructors")]
+pub async fn create_instructor(
+    repo: Data<InstructorRepo>,
+    body: Json<InstructorInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+    let instructor = body.to_instructor(Uuid::new_v4(), 1);
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(create_profile(&mut repo_guard, instructor))
+}
 
 #[post("/instructors/{new}")]
 pub async fn add_instructor(
     repo: Data<InstructorRepo>,
     new: Path<String>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&new);
-
     match repo.lock() {
-        Ok(mut repo) => {
-            repo.insert(sanitized);
-            Ok(HttpResponse::Created().finish())
-        }
+        Ok(mut repo) => Ok(add_profile_by_name(&mut repo, &new)),
         Err(_) => Err(actix_web::error::ErrorInternalServerError(
             "Failed to acquire repo lock",
         )),
@@ -24,11 +176,13 @@ pub async fn add_instructor(
 }
 
 #[get("/instructors")]
-pub async fn get_instructors(repo: Data<InstructorRepo>) -> Result<HttpResponse, actix_web::Error> {
+pub async fn get_instructors(
+    repo: Data<InstructorRepo>,
+    query: Query<SearchQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
     match repo.lock() {
         Ok(repo) => {
-            let mut instructors: Vec<String> = repo.iter().cloned().collect();
-            instructors.sort();
+            let instructors = search(repo.iter().cloned().collect(), |i| &i.name, &query);
 
             Ok(HttpResponse::Ok()
                 .content_type("application/json; charset=utf-8")
@@ -40,24 +194,109 @@ pub async fn get_instructors(repo: Data<InstructorRepo>) -> Result<HttpResponse,
     }
 }
 
+#[get("/instructors/{id}")]
+pub async fn get_instructor(
+    repo: Data<InstructorRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    match repo_guard.iter().find(|i| i.id == *path) {
+        Some(instructor) => Ok(versioned_response(&req, instructor, instructor.version)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[patch("/instructors/{id}")]
+pub async fn update_instructor(
+    repo: Data<InstructorRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Json<InstructorInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Instructor)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(update_profile(
+        &mut repo_guard,
+        &req,
+        target_id,
+        |version| body.to_instructor(target_id, version),
+        |from, to| refs.reassign(from, to),
+    ))
+}
+
 #[delete("/instructors/{i}")]
 pub async fn delete_instructor(
     repo: Data<InstructorRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
     i: Path<String>,
+    options: Query<DeleteOptions>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&i);
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Instructor)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
 
-    match repo.lock() {
-        Ok(mut repo) => {
-            if repo.contains(&sanitized) {
-                repo.remove(&sanitized);
-                Ok(HttpResponse::Ok().finish())
-            } else {
-                Ok(HttpResponse::NotFound().finish())
-            }
-        }
-        Err(_) => Err(actix_web::error::ErrorInternalServerError(
-            "Failed to acquire repo lock",
-        )),
-    }
+    Ok(delete_profile(
+        &mut repo_guard,
+        &mut refs,
+        &i,
+        options.reassign_to.as_deref(),
+    ))
+}
+
+#[post("/instructors/{i}/rename")]
+pub async fn rename_instructor(
+    repo: Data<InstructorRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    i: Path<String>,
+    body: Json<RenameRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Instructor)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(rename_profile(&mut repo_guard, &i, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
+}
+
+#[post("/instructors/merge")]
+pub async fn merge_instructors(
+    repo: Data<InstructorRepo>,
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    body: Json<MergeRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = EngagementRefs::lock(&engagements, &audit, &trash, &req, Role::Instructor)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(merge_profiles(&mut repo_guard, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
 }
@@ -0,0 +1,202 @@
+use actix_web::HttpResponse;
+use serde_json::json;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::api::Engagement;
+use crate::audit::{AuditEntry, Operation};
+use crate::names::normalize_name;
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{HostRepo, InstructorRepo};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeleteOptions {
+    pub reassign_to: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Instructor,
+    Host,
+}
+
+impl Role {
+    fn label(&self) -> &'static str {
+        match self {
+            Role::Instructor => "instructor",
+            Role::Host => "host",
+        }
+    }
+
+    pub fn field<'a>(&self, eng: &'a Engagement) -> &'a str {
+        match self {
+            Role::Instructor => &eng.instructor,
+            Role::Host => &eng.host,
+        }
+    }
+
+    pub fn set(&self, eng: &mut Engagement, name: &str) {
+        match self {
+            Role::Instructor => eng.instructor = name.to_string(),
+            Role::Host => eng.host = name.to_string(),
+        }
+    }
+}
+
+// Taken once so the profile locks are not held while engagements are written
+pub struct KnownNames {
+    instructors: HashSet<String>,
+    hosts: HashSet<String>,
+}
+
+impl KnownNames {
+    pub fn load(instructors: &InstructorRepo, hosts: &HostRepo) -> Result<Self, actix_web::Error> {
+        let instructors = instructors
+            .lock()
+            .map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire instructor lock")
+            })?
+            .iter()
+            .map(|i| i.name.clone())
+            .collect();
+        let hosts = hosts
+            .lock()
+            .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire host lock"))?
+            .iter()
+            .map(|h| h.name.clone())
+            .collect();
+
+        Ok(Self { instructors, hosts })
+    }
+
+    // Only changed references are checked, so engagements created before
+    // profiles existed stay editable
+    pub fn check(&self, before: Option<&Engagement>, after: &Engagement) -> Result<(), String> {
+        for (role, names) in [
+            (Role::Instructor, &self.instructors),
+            (Role::Host, &self.hosts),
+        ] {
+            let name = role.field(after);
+            let unchanged = before.is_some_and(|before| role.field(before) == name);
+            if !unchanged && !names.contains(name) {
+                return Err(format!(
+                    "Unknown {}: {}. Create the {} profile first",
+                    role.label(),
+                    name,
+                    role.label()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn unknown_reference(details: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Unknown reference",
+            "details": details
+        }))
+}
+
+fn dependents(repo: &HashSet<Engagement>, role: Role, name: &str) -> Vec<Uuid> {
+    let mut ids: Vec<Uuid> = repo
+        .iter()
+        .filter(|e| role.field(e) == name)
+        .map(|e| e.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn in_use(role: Role, name: &str, dependents: &[Uuid]) -> HttpResponse {
+    HttpResponse::Conflict()
+        .content_type("application/json")
+        .json(json!({
+            "error": format!("The {} is still referenced", role.label()),
+            "details": format!(
+                "{} engagements refer to {}; pass reassign_to to move them first",
+                dependents.len(),
+                name
+            ),
+            "engagements": dependents
+        }))
+}
+
+// Trashed engagements follow too, so they can still be restored
+pub fn reassign(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    trash: &mut [TrashEntry],
+    actor: &str,
+    role: Role,
+    from: &str,
+    to: &str,
+) -> Vec<Uuid> {
+    let affected: Vec<Engagement> = repo
+        .iter()
+        .filter(|e| role.field(e) == from)
+        .cloned()
+        .collect();
+
+    let last_updated_by = format!("{} {}", actor, chrono::Utc::now().format("%Y-%m-%d"));
+    let mut ids = Vec::new();
+    for current in affected {
+        let mut eng = current.clone();
+        role.set(&mut eng, to);
+        eng.last_updated_by = Some(last_updated_by.clone());
+        eng.version += 1;
+
+        audit.push(AuditEntry::new(
+            Operation::Update,
+            actor,
+            Some(&current),
+            Some(&eng),
+        ));
+        ids.push(eng.id);
+        repo.replace(eng);
+    }
+    for entry in trash.iter_mut() {
+        if let TrashedRecord::Engagement(eng) = &mut entry.record {
+            if role.field(eng) == from {
+                role.set(eng, to);
+            }
+        }
+    }
+    ids.sort();
+    ids
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn release(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    trash: &mut [TrashEntry],
+    actor: &str,
+    role: Role,
+    name: &str,
+    reassign_to: Option<&str>,
+    exists: impl Fn(&str) -> bool,
+) -> Result<Vec<Uuid>, HttpResponse> {
+    let Some(to) = reassign_to.map(normalize_name) else {
+        let dependents = dependents(repo, role, name);
+        return if dependents.is_empty() {
+            Ok(dependents)
+        } else {
+            Err(in_use(role, name, &dependents))
+        };
+    };
+
+    if to == name || !exists(&to) {
+        return Err(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid reassign target",
+                "details": format!("reassign_to must name another existing {}", role.label())
+            })));
+    }
+
+    Ok(reassign(repo, audit, trash, actor, role, name, &to))
+}
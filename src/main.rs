@@ -16,19 +16,37 @@ use std::{
 };
 
 mod api;
+mod audit;
 mod backup;
+mod bulk;
+mod calendar;
+mod conflicts;
+mod contact;
+mod csv_io;
 mod hosts;
 mod instructors;
+mod integrity;
+mod merge_patch;
+mod names;
+mod numbering;
+mod preconditions;
+mod profiles;
 mod routing;
 mod security_headers;
+mod series;
+mod stats;
 mod translations;
 mod translators;
+mod trash;
 mod types;
+mod workflow;
 
 use api::Engagement;
 use backup::{BackupConfig, BackupSystem};
+use conflicts::ConflictConfig;
 use security_headers::SecurityHeaders;
 use translations::Translation;
+use trash::TrashConfig;
 use types::*;
 
 #[actix_web::main]
@@ -47,15 +65,21 @@ async fn main() -> std::io::Result<()> {
     let hosts = HostRepo::new();
     let translations: Arc<Mutex<Vec<Translation>>> = Arc::new(Mutex::new(Vec::new()));
     let translators = TranslatorRepo::new();
+    let audit_log = AuditRepo::new();
+    let trash = TrashRepo::new();
+    let translation_ids = TranslationIds::new();
 
     let backup_engagements = engagements.clone();
     let backup_instructors = instructors.clone();
     let backup_hosts = hosts.clone();
     let backup_translations = translations.clone();
     let backup_translators = translators.clone();
+    let backup_audit_log = audit_log.clone();
+    let backup_trash = trash.clone();
+    let backup_translation_ids = translation_ids.clone();
 
     // let load_instructors = instructors.clone();
-    // load_instructors_from_file(load_instructors)?; // used once to seed instructors 
+    // load_instructors_from_file(load_instructors)?; // used once to seed instructors
 
     if let Err(e) = configure_backup_system(
         backup_engagements.clone(),
@@ -63,12 +87,42 @@ async fn main() -> std::io::Result<()> {
         backup_hosts,
         backup_translations,
         backup_translators,
+        backup_audit_log,
+        backup_trash,
+        backup_translation_ids,
     )
     .await
     {
         log::error!("Failed to configure backup system: {}", e);
     }
 
+    // Translations created before ids were allocated may share an id
+    for (old_id, new_id) in translations::repair_ids(
+        &mut translations.lock().unwrap(),
+        &mut trash.lock().unwrap(),
+        &mut translation_ids.lock().unwrap(),
+    ) {
+        log::warn!("Re-keyed duplicate translation id {} to {}", old_id, new_id);
+    }
+
+    // Names stored before normalization would stop matching their profiles
+    // as soon as an edit normalized them
+    for (old_name, new_name) in profiles::normalize_stored_names(
+        &mut engagements.lock().unwrap(),
+        &mut instructors.lock().unwrap(),
+        &mut hosts.lock().unwrap(),
+        &mut translations.lock().unwrap(),
+        &mut translators.lock().unwrap(),
+        &mut audit_log.lock().unwrap(),
+        &mut trash.lock().unwrap(),
+    ) {
+        log::warn!("Normalized stored name {:?} to {:?}", old_name, new_name);
+    }
+
+    let conflict_config = ConflictConfig::from_env();
+    let trash_config = TrashConfig::from_env();
+    trash::start_purge_task(trash.clone(), trash_config.clone());
+
     let limiter = LimiterBuilder::new()
         .with_duration(chrono::Duration::minutes(1))
         .with_num_requests(60)
@@ -84,13 +138,23 @@ async fn main() -> std::io::Result<()> {
             .app_data(Data::new(hosts.clone()))
             .app_data(Data::new(translations.clone()))
             .app_data(Data::new(translators.clone()))
+            .app_data(Data::new(audit_log.clone()))
+            .app_data(Data::new(trash.clone()))
+            .app_data(Data::new(translation_ids.clone()))
+            .app_data(Data::new(conflict_config.clone()))
+            .app_data(Data::new(trash_config.clone()))
             .service(
                 web::scope("")
                     .configure(routing::config_eng_paths)
                     .configure(routing::config_ins_paths)
                     .configure(routing::config_hosts_paths)
                     .configure(routing::config_translation_paths)
-                    .configure(routing::config_translators_paths),
+                    .configure(routing::config_translators_paths)
+                    .configure(routing::config_calendar_paths)
+                    .configure(routing::config_series_paths)
+                    .configure(routing::config_stats_paths)
+                    .configure(routing::config_duplicates_paths)
+                    .configure(routing::config_trash_paths),
             )
     })
     .bind_rustls(&listen_addr, rustls_config)?
@@ -130,12 +194,16 @@ fn load_rustls_config(cert_path: &str, key_path: &str) -> std::io::Result<Server
     Ok(config)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn configure_backup_system(
     engagements: Arc<Mutex<HashSet<Engagement>>>,
     instructors: InstructorRepo,
     hosts: HostRepo,
     translations: Arc<Mutex<Vec<Translation>>>,
     translators: TranslatorRepo,
+    audit_log: AuditRepo,
+    trash: TrashRepo,
+    translation_ids: TranslationIds,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let config = BackupConfig::from_env()?;
     let backup_system = BackupSystem::new(
@@ -144,67 +212,83 @@ async fn configure_backup_system(
         hosts.0.clone(),
         translations.clone(),
         translators.0.clone(),
+        audit_log.0.clone(),
+        trash.0.clone(),
+        translation_ids.0.clone(),
         config,
     )
     .await?;
 
-    {
-        let (
-            mut engagements_guard,
-            mut instructors_guard,
-            mut hosts_guard,
-            mut translations_guard,
-            mut translators_guard,
-        ) = (
-            engagements.lock().unwrap(),
-            instructors.lock().unwrap(),
-            hosts.lock().unwrap(),
-            translations.lock().unwrap(),
-            translators.lock().unwrap(),
-        );
-
-        if engagements_guard.is_empty()
-            || instructors_guard.is_empty()
-            || hosts_guard.is_empty()
-            || translations_guard.is_empty()
-            || translators_guard.is_empty()
-        {
-            match backup_system.restore_latest_backup().await {
-                Ok((
-                    restored_engagements,
-                    restored_instructors,
-                    restored_hosts,
-                    restored_translations,
-                    restored_translators,
-                )) => {
-                    if engagements_guard.is_empty() {
-                        *engagements_guard = restored_engagements;
-                        log::info!("Successfully restored engagements from latest backup");
-                    }
-
-                    if hosts_guard.is_empty() {
-                        *hosts_guard = restored_hosts;
-                        log::info!("Successfully restored hosts from latest backup");
-                    }
-
-                    if instructors_guard.is_empty() {
-                        *instructors_guard = restored_instructors;
-                        log::info!("Successfully restored instructors from latest backup");
-                    }
-
-                    if translations_guard.is_empty() {
-                        *translations_guard = restored_translations;
-                        log::info!("Successfully restored translations from latest backup");
-                    }
-
-                    if translators_guard.is_empty() {
-                        *translators_guard = restored_translators;
-                        log::info!("Successfully restored translators from latest backup");
-                    }
+    // Check for empty repos without holding the locks across the restore await
+    let needs_restore = engagements.lock().unwrap().is_empty()
+        || instructors.lock().unwrap().is_empty()
+        || hosts.lock().unwrap().is_empty()
+        || translations.lock().unwrap().is_empty()
+        || translators.lock().unwrap().is_empty()
+        || audit_log.lock().unwrap().is_empty();
+
+    if needs_restore {
+        match backup_system.restore_latest_backup().await {
+            Ok(restored) => {
+                let (
+                    mut engagements_guard,
+                    mut audit_guard,
+                    mut instructors_guard,
+                    mut hosts_guard,
+                    mut translations_guard,
+                    mut translators_guard,
+                    mut trash_guard,
+                    mut translation_ids_guard,
+                ) = (
+                    engagements.lock().unwrap(),
+                    audit_log.lock().unwrap(),
+                    instructors.lock().unwrap(),
+                    hosts.lock().unwrap(),
+                    translations.lock().unwrap(),
+                    translators.lock().unwrap(),
+                    trash.lock().unwrap(),
+                    translation_ids.lock().unwrap(),
+                );
+
+                if engagements_guard.is_empty() {
+                    *engagements_guard = restored.engagements;
+                    log::info!("Successfully restored engagements from latest backup");
                 }
-                Err(e) => {
-                    log::error!("Failed to restore data from backup: {}", e);
+
+                if audit_guard.is_empty() {
+                    *audit_guard = restored.audit_log;
+                    log::info!("Successfully restored audit log from latest backup");
+                }
+
+                if hosts_guard.is_empty() {
+                    *hosts_guard = restored.hosts;
+                    log::info!("Successfully restored hosts from latest backup");
                 }
+
+                if instructors_guard.is_empty() {
+                    *instructors_guard = restored.instructors;
+                    log::info!("Successfully restored instructors from latest backup");
+                }
+
+                if translations_guard.is_empty() {
+                    *translations_guard = restored.translations;
+                    log::info!("Successfully restored translations from latest backup");
+                }
+
+                if translators_guard.is_empty() {
+                    *translators_guard = restored.translators;
+                    log::info!("Successfully restored translators from latest backup");
+                }
+
+                if trash_guard.is_empty() {
+                    *trash_guard = restored.trash;
+                    log::info!("Successfully restored trash from latest backup");
+                }
+
+                *translation_ids_guard = (*translation_ids_guard).max(restored.next_translation_id);
+            }
+            Err(e) => {
+                log::error!("Failed to restore data from backup: {}", e);
             }
         }
     }
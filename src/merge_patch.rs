@@ -0,0 +1,62 @@
+use actix_web::{http::header::CONTENT_TYPE, web::Bytes, HttpRequest, HttpResponse};
+use serde_json::{json, Map, Value};
+
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+// RFC 7396: null removes a member, objects merge recursively
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+// Plain application/json is accepted too
+pub fn parse_merge_patch(req: &HttpRequest, body: &Bytes) -> Result<Value, HttpResponse> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .unwrap_or_default();
+
+    if content_type != MERGE_PATCH_CONTENT_TYPE && content_type != "application/json" {
+        return Err(HttpResponse::UnsupportedMediaType()
+            .content_type("application/json")
+            .insert_header(("Accept-Patch", MERGE_PATCH_CONTENT_TYPE))
+            .json(json!({
+                "error": "Unsupported media type",
+                "details": format!("Send the patch as {}", MERGE_PATCH_CONTENT_TYPE)
+            })));
+    }
+
+    match serde_json::from_slice::<Value>(body) {
+        Ok(patch @ Value::Object(_)) => Ok(patch),
+        Ok(_) => Err(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid patch",
+                "details": "A merge patch must be a JSON object"
+            }))),
+        Err(e) => Err(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Invalid patch",
+                "details": e.to_string()
+            }))),
+    }
+}
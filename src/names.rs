@@ -0,0 +1,240 @@
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use icu_normalizer::{ComposingNormalizer, DecomposingNormalizer};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::types::{HostRepo, InstructorRepo, TranslatorRepo};
+
+// NFC with runs of whitespace collapsed
+fn canonical(name: &str) -> String {
+    ComposingNormalizer::new_nfc()
+        .normalize(name)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// Names are matched exactly after this, so "José" typed with a combining
+// accent and "José  " both become the same "José"
+pub fn normalize_name(name: &str) -> String {
+    canonical(&ammonia::clean(name))
+}
+
+// Stored names may predate normalization
+pub fn name_matches(stored: &str, key: &str) -> bool {
+    canonical(stored) == normalize_name(key)
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+// Drops accents, case and punctuation, so "St. María" folds to "st maria"
+pub fn fold_name(name: &str) -> String {
+    let mut folded = String::new();
+    for c in DecomposingNormalizer::new_nfkd()
+        .normalize(name)
+        .chars()
+        .filter(|c| !is_combining_mark(*c))
+    {
+        if c.is_alphanumeric() {
+            folded.extend(c.to_lowercase());
+        } else {
+            folded.push(' ');
+        }
+    }
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// At most a quarter of the shorter name may differ, which keeps short names
+// like "Ana" and "Eva" apart
+fn likely_duplicates(a: &str, b: &str, max_distance: usize) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a_len, b_len) = (a.chars().count(), b.chars().count());
+    if a_len.abs_diff(b_len) > max_distance {
+        return false;
+    }
+    let distance = edit_distance(a, b);
+    distance <= max_distance && distance * 4 <= a_len.min(b_len)
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct Candidate {
+    pub id: Uuid,
+    pub name: String,
+}
+
+// Matching is transitive, so a cluster may hold names that only match
+// through another
+fn cluster(mut candidates: Vec<Candidate>, max_distance: usize) -> Vec<Vec<Candidate>> {
+    candidates.sort_by(|a, b| a.name.cmp(&b.name));
+    let folded: Vec<String> = candidates.iter().map(|c| fold_name(&c.name)).collect();
+
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            if likely_duplicates(&folded[i], &folded[j], max_distance) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<Candidate>> = Vec::new();
+    let mut cluster_of = vec![usize::MAX; candidates.len()];
+    for (i, candidate) in candidates.into_iter().enumerate() {
+        let r = root(&mut parent, i);
+        if cluster_of[r] == usize::MAX {
+            cluster_of[r] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of[r]].push(candidate);
+    }
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+// Best first: whole name, start of name, start of a later word, anywhere
+fn match_rank(name: &str, query: &str) -> Option<u8> {
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.split(' ').any(|word| word.starts_with(query)) {
+        Some(2)
+    } else if name.contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+const MAX_SEARCH_LIMIT: usize = 100;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+pub fn search<T>(mut items: Vec<T>, name: impl Fn(&T) -> &str, query: &SearchQuery) -> Vec<T> {
+    let folded_query = query.q.as_deref().map(fold_name).unwrap_or_default();
+    if folded_query.is_empty() {
+        items.sort_by(|a, b| name(a).cmp(name(b)));
+        if let Some(limit) = query.limit {
+            items.truncate(limit);
+        }
+        return items;
+    }
+
+    let mut ranked: Vec<(u8, T)> = items
+        .into_iter()
+        .filter_map(|item| {
+            match_rank(&fold_name(name(&item)), &folded_query).map(|rank| (rank, item))
+        })
+        .collect();
+    ranked
+        .sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then_with(|| name(a).cmp(name(b))));
+    ranked.truncate(
+        query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .min(MAX_SEARCH_LIMIT),
+    );
+
+    ranked.into_iter().map(|(_, item)| item).collect()
+}
+
+fn default_max_distance() -> usize {
+    2
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DuplicatesQuery {
+    #[serde(default = "default_max_distance")]
+    pub max_distance: usize,
+}
+
+#[get("/duplicates")]
+pub async fn get_duplicates(
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    translators: Data<TranslatorRepo>,
+    query: Query<DuplicatesQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let instructors: Vec<Candidate> = instructors
+        .lock()
+        .map_err(|_| {
+            actix_web::error::ErrorInternalServerError("Failed to acquire instructor lock")
+        })?
+        .iter()
+        .map(|i| Candidate {
+            id: i.id,
+            name: i.name.clone(),
+        })
+        .collect();
+    let hosts: Vec<Candidate> = hosts
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire host lock"))?
+        .iter()
+        .map(|h| Candidate {
+            id: h.id,
+            name: h.name.clone(),
+        })
+        .collect();
+    let translators: Vec<Candidate> = translators
+        .lock()
+        .map_err(|_| {
+            actix_web::error::ErrorInternalServerError("Failed to acquire translator lock")
+        })?
+        .iter()
+        .map(|t| Candidate {
+            id: t.id,
+            name: t.name.clone(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(json!({
+            "max_distance": query.max_distance,
+            "instructors": cluster(instructors, query.max_distance),
+            "hosts": cluster(hosts, query.max_distance),
+            "translators": cluster(translators, query.max_distance)
+        })))
+}
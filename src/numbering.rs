@@ -0,0 +1,298 @@
+use actix_web::{
+    post,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    ops::RangeBounds,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::Engagement;
+use crate::audit::{actor, AuditEntry, Operation};
+use crate::preconditions::etag;
+use crate::types::AuditRepo;
+
+// Numbers used to be stored as strings, so both 12 and "12" are accepted
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum NumberRepr {
+    Int(usize),
+    Text(String),
+}
+
+impl NumberRepr {
+    fn parse(self) -> Result<Option<usize>, String> {
+        match self {
+            NumberRepr::Int(n) => Ok(Some(n)),
+            NumberRepr::Text(text) if text.trim().is_empty() => Ok(None),
+            NumberRepr::Text(text) => text
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid number format: {}", text)),
+        }
+    }
+}
+
+// Blank or unparseable legacy values become None rather than failing a
+// whole backup restore; POST /engs/renumber fills the gaps
+pub fn legacy_number<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<NumberRepr> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(value.and_then(|value| value.parse().ok().flatten()))
+}
+
+pub fn optional_number<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<NumberRepr> = serde::Deserialize::deserialize(deserializer)?;
+    match value {
+        Some(value) => value.parse().map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+pub fn required_number<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: NumberRepr = serde::Deserialize::deserialize(deserializer)?;
+    value
+        .parse()
+        .map_err(serde::de::Error::custom)?
+        .ok_or_else(|| serde::de::Error::custom("A number is required"))
+}
+
+// Changing a number in place would leave duplicates or gaps, so numbers
+// only change through POST /engs/{id}/move
+pub fn check_number_unchanged(before: &Engagement, after: &Engagement) -> Result<(), String> {
+    if before.number == after.number {
+        Ok(())
+    } else {
+        Err(format!(
+            "Number cannot be changed by an edit; use POST /engs/{}/move instead",
+            before.id
+        ))
+    }
+}
+
+fn set_number(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    eng: &Engagement,
+    number: Option<usize>,
+) {
+    let mut updated = eng.clone();
+    updated.number = number;
+    updated.version += 1;
+    audit.push(AuditEntry::new(
+        Operation::Update,
+        actor,
+        Some(eng),
+        Some(&updated),
+    ));
+    repo.replace(updated);
+}
+
+fn shift_numbers(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    range: impl RangeBounds<usize>,
+    up: bool,
+) {
+    let to_update: Vec<Engagement> = repo
+        .iter()
+        .filter(|e| e.number.is_some_and(|n| range.contains(&n)))
+        .cloned()
+        .collect();
+
+    for eng in to_update {
+        let number = eng.number.map(|n| if up { n + 1 } else { n - 1 });
+        set_number(repo, audit, actor, &eng, number);
+    }
+}
+
+pub fn insert_numbered(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    new_eng: Engagement,
+) {
+    place_numbered(repo, audit, actor, Operation::Create, new_eng);
+}
+
+// A slot past the end of the current numbering becomes the next free number
+pub fn restore_numbered(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    mut eng: Engagement,
+) {
+    let next = repo.iter().filter_map(|e| e.number).max().unwrap_or(0) + 1;
+    eng.number = eng.number.map(|n| n.min(next));
+    place_numbered(repo, audit, actor, Operation::Restore, eng);
+}
+
+fn place_numbered(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    operation: Operation,
+    eng: Engagement,
+) {
+    if let Some(num) = eng.number {
+        if repo.iter().any(|e| e.number == Some(num)) {
+            shift_numbers(repo, audit, actor, num.., true);
+        }
+    }
+
+    audit.push(AuditEntry::new(operation, actor, None, Some(&eng)));
+    repo.insert(eng);
+}
+
+pub fn remove_numbered(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    actor: &str,
+    eng: &Engagement,
+) {
+    repo.remove(eng);
+    audit.push(AuditEntry::new(Operation::Delete, actor, Some(eng), None));
+
+    if let Some(num) = eng.number {
+        shift_numbers(repo, audit, actor, (num + 1).., false);
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct MoveRequest {
+    pub to: usize,
+}
+
+// Targets past the end are moved to the last position
+#[post("/engs/{id}/move")]
+pub async fn move_eng(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Json<MoveRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    let actor = actor(&req, None);
+
+    if body.to == 0 {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Validation failed",
+                "details": "Number must be greater than 0"
+            })));
+    }
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let mut audit_guard = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?;
+
+    let Some(eng) = repo_guard.iter().find(|e| e.id == target_id).cloned() else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let last = repo_guard
+        .iter()
+        .filter_map(|e| e.number)
+        .max()
+        .unwrap_or(0);
+    let to = match eng.number {
+        Some(from) => {
+            let to = body.to.min(last);
+            if to > from {
+                shift_numbers(
+                    &mut repo_guard,
+                    &mut audit_guard,
+                    &actor,
+                    (from + 1)..=to,
+                    false,
+                );
+            } else if to < from {
+                shift_numbers(&mut repo_guard, &mut audit_guard, &actor, to..from, true);
+            }
+            to
+        }
+        // An unnumbered engagement is inserted, which may extend the list by one
+        None => {
+            let to = body.to.min(last + 1);
+            shift_numbers(&mut repo_guard, &mut audit_guard, &actor, to.., true);
+            to
+        }
+    };
+
+    if eng.number != Some(to) {
+        set_number(&mut repo_guard, &mut audit_guard, &actor, &eng, Some(to));
+    }
+
+    let moved = repo_guard.get(&eng).cloned().ok_or_else(|| {
+        actix_web::error::ErrorInternalServerError("Engagement disappeared while moving")
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .insert_header(("ETag", etag(moved.version).to_string()))
+        .json(moved))
+}
+
+// Ties are broken by date
+#[post("/engs/renumber")]
+pub async fn renumber_engs(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    req: HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    let actor = actor(&req, None);
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let mut audit_guard = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?;
+
+    let mut numbered: Vec<Engagement> = repo_guard
+        .iter()
+        .filter(|e| e.number.is_some())
+        .cloned()
+        .collect();
+    numbered.sort_by(|a, b| {
+        a.number
+            .cmp(&b.number)
+            .then_with(|| a.date.cmp(&b.date))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let mut renumbered = 0;
+    for (index, eng) in numbered.iter().enumerate() {
+        let number = Some(index + 1);
+        if eng.number != number {
+            set_number(&mut repo_guard, &mut audit_guard, &actor, eng, number);
+            renumbered += 1;
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(json!({
+            "numbered": numbered.len(),
+            "renumbered": renumbered
+        })))
+}
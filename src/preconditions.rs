@@ -0,0 +1,70 @@
+use actix_web::{
+    http::header::{EntityTag, IfMatch, IfNoneMatch},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use serde_json::json;
+
+pub fn etag(version: u64) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+pub fn versioned_response<T: serde::Serialize>(
+    req: &HttpRequest,
+    current: &T,
+    current_version: u64,
+) -> HttpResponse {
+    let tag = etag(current_version);
+    let unchanged = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(&tag)),
+        None => false,
+    };
+
+    if unchanged {
+        HttpResponse::NotModified()
+            .insert_header(("ETag", tag.to_string()))
+            .finish()
+    } else {
+        HttpResponse::Ok()
+            .content_type("application/json; charset=utf-8")
+            .insert_header(("ETag", tag.to_string()))
+            .json(current)
+    }
+}
+
+// Writes without If-Match get 428 so clients cannot skip the check by
+// accident; a 412 carries the server copy so the client can merge and retry
+pub fn check_if_match<T: serde::Serialize>(
+    req: &HttpRequest,
+    current: &T,
+    current_version: u64,
+) -> Result<(), HttpResponse> {
+    match req.get_header::<IfMatch>() {
+        Some(IfMatch::Any) => Ok(()),
+        Some(IfMatch::Items(tags)) => {
+            if tags.iter().any(|tag| tag.strong_eq(&etag(current_version))) {
+                Ok(())
+            } else {
+                Err(HttpResponse::PreconditionFailed()
+                    .content_type("application/json")
+                    .insert_header(("ETag", etag(current_version).to_string()))
+                    .json(json!({
+                        "error": "Version mismatch",
+                        "details": format!(
+                            "The record was modified by someone else; current version is {}",
+                            current_version
+                        ),
+                        "current": current
+                    })))
+            }
+        }
+        None => Err(
+            HttpResponse::build(actix_web::http::StatusCode::PRECONDITION_REQUIRED)
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Precondition required",
+                    "details": "Send the record's version in an If-Match header"
+                })),
+        ),
+    }
+}
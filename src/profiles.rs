@@ -0,0 +1,469 @@
+use actix_web::{HttpRequest, HttpResponse};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+use crate::api::Engagement;
+use crate::audit::{actor, AuditEntry, Operation};
+use crate::hosts::Host;
+use crate::instructors::Instructor;
+use crate::integrity::{reassign, release, Role};
+use crate::names::{name_matches, normalize_name};
+use crate::preconditions::{check_if_match, etag};
+use crate::translations::Translation;
+use crate::translators::{normalize_assignees, reassign_translations, Translator};
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{AuditRepo, TrashRepo};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RenameRequest {
+    pub name: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct MergeRequest {
+    pub from: Vec<String>,
+    pub into: String,
+}
+
+// Other records refer to profiles by name, so renames and merges are carried
+// over to them by the caller
+pub trait Profile: Clone + Eq + std::hash::Hash + serde::Serialize {
+    const KIND: &'static str;
+    const REFERRERS: &'static str;
+
+    fn from_name(name: &str) -> Self;
+    fn id(&self) -> Uuid;
+    fn name(&self) -> &str;
+    fn set_name(&mut self, name: String);
+    fn version(&self) -> u64;
+    fn set_version(&mut self, version: u64);
+    fn absorb(&mut self, other: &Self);
+}
+
+// Backups written before profiles existed hold bare names
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+pub enum StoredProfile<P> {
+    Profile(P),
+    Name(String),
+}
+
+impl<P: Profile> StoredProfile<P> {
+    pub fn into_profile(self) -> P {
+        match self {
+            StoredProfile::Profile(profile) => profile,
+            StoredProfile::Name(name) => P::from_name(&name),
+        }
+    }
+}
+
+pub fn validation_failed(details: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Validation failed",
+            "details": details
+        }))
+}
+
+fn name_taken<P: Profile>(name: &str) -> HttpResponse {
+    HttpResponse::Conflict()
+        .content_type("application/json")
+        .json(json!({
+            "error": format!("Duplicate {}", P::KIND),
+            "details": format!("The name {} is already taken by another {}", name, P::KIND)
+        }))
+}
+
+fn unknown_profile<P: Profile>(key: &str) -> HttpResponse {
+    HttpResponse::NotFound()
+        .content_type("application/json")
+        .json(json!({
+            "error": format!("Unknown {}", P::KIND),
+            "details": format!("No {} with id or name {}", P::KIND, key)
+        }))
+}
+
+fn invalid_merge(details: &str) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Invalid merge",
+            "details": details
+        }))
+}
+
+pub fn find_profile<P: Profile>(repo: &HashSet<P>, key: &str) -> Option<P> {
+    let id = Uuid::parse_str(key).ok();
+    repo.iter()
+        .find(|x| Some(x.id()) == id || name_matches(x.name(), key))
+        .cloned()
+}
+
+fn name_in_use<P: Profile>(repo: &HashSet<P>, id: Uuid, name: &str) -> bool {
+    repo.iter().any(|x| x.id() != id && x.name() == name)
+}
+
+fn created<P: Profile>(profile: &P) -> HttpResponse {
+    HttpResponse::Created()
+        .content_type("application/json; charset=utf-8")
+        .insert_header(("ETag", etag(profile.version()).to_string()))
+        .json(profile)
+}
+
+fn saved<P: Profile>(profile: &P, body: serde_json::Value) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .insert_header(("ETag", etag(profile.version()).to_string()))
+        .json(body)
+}
+
+pub fn create_profile<P: Profile>(repo: &mut HashSet<P>, profile: P) -> HttpResponse {
+    if name_in_use(repo, profile.id(), profile.name()) {
+        return name_taken::<P>(profile.name());
+    }
+    repo.insert(profile.clone());
+
+    created(&profile)
+}
+
+// Adding a name that already exists returns the existing profile
+pub fn add_profile_by_name<P: Profile>(repo: &mut HashSet<P>, name: &str) -> HttpResponse {
+    let name = normalize_name(name);
+    if name.is_empty() {
+        return validation_failed("Name must not be empty".to_string());
+    }
+
+    let profile = match repo.iter().find(|x| x.name() == name) {
+        Some(existing) => existing.clone(),
+        None => {
+            let profile = P::from_name(&name);
+            repo.insert(profile.clone());
+            profile
+        }
+    };
+    created(&profile)
+}
+
+pub fn update_profile<P: Profile, R>(
+    repo: &mut HashSet<P>,
+    req: &HttpRequest,
+    id: Uuid,
+    to_profile: impl FnOnce(u64) -> P,
+    mut reassign: impl FnMut(&str, &str) -> Vec<R>,
+) -> HttpResponse {
+    let Some(current) = repo.iter().find(|x| x.id() == id) else {
+        return HttpResponse::NotFound().finish();
+    };
+    if let Err(response) = check_if_match(req, current, current.version()) {
+        return response;
+    }
+
+    let updated = to_profile(current.version() + 1);
+    if name_in_use(repo, id, updated.name()) {
+        return name_taken::<P>(updated.name());
+    }
+    if updated.name() != current.name() {
+        reassign(current.name(), updated.name());
+    }
+    repo.replace(updated.clone());
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .insert_header(("ETag", etag(updated.version()).to_string()))
+        .json(updated)
+}
+
+pub fn rename_profile<P: Profile, R: serde::Serialize>(
+    repo: &mut HashSet<P>,
+    key: &str,
+    request: &RenameRequest,
+    mut reassign: impl FnMut(&str, &str) -> Vec<R>,
+) -> HttpResponse {
+    let name = normalize_name(&request.name);
+    if name.is_empty() {
+        return validation_failed("Name must not be empty".to_string());
+    }
+
+    let Some(mut profile) = find_profile(repo, key) else {
+        return HttpResponse::NotFound().finish();
+    };
+    if name_in_use(repo, profile.id(), &name) {
+        return name_taken::<P>(&name);
+    }
+
+    let referrers = reassign(profile.name(), &name);
+    profile.set_name(name);
+    profile.set_version(profile.version() + 1);
+    repo.replace(profile.clone());
+
+    saved(
+        &profile,
+        json!({ (P::KIND): profile, (P::REFERRERS): referrers }),
+    )
+}
+
+pub fn merge_profiles<P: Profile, R: serde::Serialize + Ord>(
+    repo: &mut HashSet<P>,
+    request: &MergeRequest,
+    mut reassign: impl FnMut(&str, &str) -> Vec<R>,
+) -> HttpResponse {
+    if request.from.is_empty() {
+        return invalid_merge(&format!("Name at least one {} to merge", P::KIND));
+    }
+
+    let Some(mut into) = find_profile(repo, &request.into) else {
+        return unknown_profile::<P>(&request.into);
+    };
+    let mut sources: Vec<P> = Vec::new();
+    for key in &request.from {
+        let Some(source) = find_profile(repo, key) else {
+            return unknown_profile::<P>(key);
+        };
+        if source == into {
+            return invalid_merge("A profile cannot be merged into itself");
+        }
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    let mut referrers = Vec::new();
+    for source in &sources {
+        into.absorb(source);
+        referrers.extend(reassign(source.name(), into.name()));
+        repo.remove(source);
+    }
+    referrers.sort();
+    referrers.dedup();
+    into.set_version(into.version() + 1);
+    repo.replace(into.clone());
+
+    saved(
+        &into,
+        json!({
+            (P::KIND): into,
+            "merged": sources.iter().map(|s| s.id()).collect::<Vec<Uuid>>(),
+            (P::REFERRERS): referrers
+        }),
+    )
+}
+
+// The records an instructor or host edit writes to, locked in the same order
+// as every other engagement write
+pub struct EngagementRefs<'a> {
+    engagements: MutexGuard<'a, HashSet<Engagement>>,
+    audit: MutexGuard<'a, Vec<AuditEntry>>,
+    trash: MutexGuard<'a, Vec<TrashEntry>>,
+    actor: String,
+    role: Role,
+}
+
+impl<'a> EngagementRefs<'a> {
+    pub fn lock(
+        engagements: &'a Mutex<HashSet<Engagement>>,
+        audit: &'a AuditRepo,
+        trash: &'a TrashRepo,
+        req: &HttpRequest,
+        role: Role,
+    ) -> Result<Self, actix_web::Error> {
+        Ok(Self {
+            engagements: engagements.lock().map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire repo lock")
+            })?,
+            audit: audit.lock().map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire audit lock")
+            })?,
+            trash: trash.lock().map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire trash lock")
+            })?,
+            actor: actor(req, None),
+            role,
+        })
+    }
+
+    pub fn reassign(&mut self, from: &str, to: &str) -> Vec<Uuid> {
+        reassign(
+            &mut self.engagements,
+            &mut self.audit,
+            &mut self.trash,
+            &self.actor,
+            self.role,
+            from,
+            to,
+        )
+    }
+}
+
+// The records a translator edit writes to
+pub struct TranslationRefs<'a> {
+    translations: MutexGuard<'a, Vec<Translation>>,
+    trash: MutexGuard<'a, Vec<TrashEntry>>,
+    actor: String,
+}
+
+impl<'a> TranslationRefs<'a> {
+    pub fn lock(
+        translations: &'a Mutex<Vec<Translation>>,
+        trash: &'a TrashRepo,
+        req: &HttpRequest,
+    ) -> Result<Self, actix_web::Error> {
+        Ok(Self {
+            translations: translations.lock().map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire repo lock")
+            })?,
+            trash: trash.lock().map_err(|_| {
+                actix_web::error::ErrorInternalServerError("Failed to acquire trash lock")
+            })?,
+            actor: actor(req, None),
+        })
+    }
+
+    pub fn reassign(&mut self, from: &str, to: &str) -> Vec<u32> {
+        reassign_translations(
+            &mut self.translations,
+            &mut self.trash,
+            &self.actor,
+            from,
+            to,
+        )
+    }
+}
+
+// Without reassign_to, deleting a profile that engagements still refer to is
+// refused; with it, they move to that profile first
+pub fn delete_profile<P: Profile>(
+    repo: &mut HashSet<P>,
+    refs: &mut EngagementRefs,
+    key: &str,
+    reassign_to: Option<&str>,
+) -> HttpResponse {
+    let Some(target) = find_profile(repo, key) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let reassigned = match release(
+        &mut refs.engagements,
+        &mut refs.audit,
+        &mut refs.trash,
+        &refs.actor,
+        refs.role,
+        target.name(),
+        reassign_to,
+        |name| repo.iter().any(|x| x.name() == name),
+    ) {
+        Ok(reassigned) => reassigned,
+        Err(response) => return response,
+    };
+    repo.remove(&target);
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .json(json!({ "reassigned": reassigned }))
+}
+
+// Profiles whose names only differed in spacing or composition are merged;
+// one already in normalized form survives
+fn normalize_profiles<P: Profile>(repo: &mut HashSet<P>) -> Vec<(String, String)> {
+    let mut profiles: Vec<P> = repo.drain().collect();
+    profiles.sort_by_key(|p| (normalize_name(p.name()) != p.name(), p.id()));
+
+    let mut renamed = Vec::new();
+    let mut by_name: HashMap<String, P> = HashMap::new();
+    for mut profile in profiles {
+        let name = normalize_name(profile.name());
+        if name != profile.name() {
+            renamed.push((profile.name().to_string(), name.clone()));
+        }
+        match by_name.get_mut(&name) {
+            Some(survivor) => {
+                survivor.absorb(&profile);
+                survivor.set_version(survivor.version() + 1);
+            }
+            None => {
+                if name != profile.name() {
+                    profile.set_name(name.clone());
+                    profile.set_version(profile.version() + 1);
+                }
+                by_name.insert(name, profile);
+            }
+        }
+    }
+
+    *repo = by_name.into_values().collect();
+    renamed
+}
+
+fn normalize_engagements(
+    repo: &mut HashSet<Engagement>,
+    audit: &mut Vec<AuditEntry>,
+    trash: &mut [TrashEntry],
+) {
+    let normalize = |eng: &mut Engagement| {
+        let mut changed = false;
+        for role in [Role::Instructor, Role::Host] {
+            let name = normalize_name(role.field(eng));
+            if name != role.field(eng) {
+                role.set(eng, &name);
+                changed = true;
+            }
+        }
+        changed
+    };
+
+    let last_updated_by = format!(
+        "{} {}",
+        NORMALIZE_ACTOR,
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    let live: Vec<Engagement> = repo.iter().cloned().collect();
+    for current in live {
+        let mut eng = current.clone();
+        if !normalize(&mut eng) {
+            continue;
+        }
+        eng.last_updated_by = Some(last_updated_by.clone());
+        eng.version += 1;
+
+        audit.push(AuditEntry::new(
+            Operation::Update,
+            NORMALIZE_ACTOR,
+            Some(&current),
+            Some(&eng),
+        ));
+        repo.replace(eng);
+    }
+    for entry in trash.iter_mut() {
+        if let TrashedRecord::Engagement(eng) = &mut entry.record {
+            normalize(eng);
+        }
+    }
+}
+
+// Who the startup normalization is recorded as in the audit log
+const NORMALIZE_ACTOR: &str = "system";
+
+// Without this an edit to an older record would normalize its references,
+// which then no longer match the profile names they came from
+pub fn normalize_stored_names(
+    engagements: &mut HashSet<Engagement>,
+    instructors: &mut HashSet<Instructor>,
+    hosts: &mut HashSet<Host>,
+    translations: &mut [Translation],
+    translators: &mut HashSet<Translator>,
+    audit: &mut Vec<AuditEntry>,
+    trash: &mut [TrashEntry],
+) -> Vec<(String, String)> {
+    let mut renamed = normalize_profiles(instructors);
+    renamed.extend(normalize_profiles(hosts));
+    renamed.extend(normalize_profiles(translators));
+
+    normalize_engagements(engagements, audit, trash);
+    normalize_assignees(translations, trash);
+
+    renamed
+}
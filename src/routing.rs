@@ -1,36 +1,102 @@
+use crate::audit::get_eng_history;
+use crate::bulk::bulk_engs;
+use crate::calendar::*;
+use crate::conflicts::get_conflicts;
+use crate::csv_io::*;
+use crate::names::get_duplicates;
+use crate::numbering::{move_eng, renumber_engs};
+use crate::series::*;
+use crate::stats::get_engagement_stats;
 use crate::translations::*;
 use crate::translators::*;
+use crate::trash::{get_trash, restore_from_trash};
+use crate::workflow::get_transitions;
 use crate::{api::*, hosts::*, instructors::*};
 use actix_web::web::ServiceConfig;
 
 pub fn config_eng_paths(cfg: &mut ServiceConfig) {
     cfg.service(add_eng);
     cfg.service(get_engs);
+    cfg.service(get_conflicts);
+    cfg.service(export_engs_csv);
+    cfg.service(import_engs_csv);
+    cfg.service(bulk_engs);
+    cfg.service(renumber_engs);
+    cfg.service(move_eng);
     cfg.service(edit_eng);
+    cfg.service(patch_eng);
     cfg.service(delete_eng);
+    cfg.service(get_eng_history);
+    cfg.service(get_transitions);
+    // Registered after the fixed /engs/... paths so they are not captured as ids
+    cfg.service(get_eng);
 }
 
 pub fn config_translation_paths(cfg: &mut ServiceConfig) {
     cfg.service(create_translation);
     cfg.service(get_translations);
+    cfg.service(get_translation);
     cfg.service(update_translation);
+    cfg.service(patch_translation);
     cfg.service(delete_translation);
 }
 
 pub fn config_ins_paths(cfg: &mut ServiceConfig) {
+    cfg.service(create_instructor);
+    // Registered before add_instructor so "merge" is not taken as a name
+    cfg.service(merge_instructors);
     cfg.service(add_instructor);
     cfg.service(get_instructors);
+    cfg.service(get_instructor);
+    cfg.service(update_instructor);
     cfg.service(delete_instructor);
+    cfg.service(rename_instructor);
 }
 
 pub fn config_hosts_paths(cfg: &mut ServiceConfig) {
+    cfg.service(create_host);
+    cfg.service(merge_hosts);
     cfg.service(add_host);
     cfg.service(get_hosts);
+    cfg.service(get_host);
+    cfg.service(update_host);
     cfg.service(delete_host);
+    cfg.service(rename_host);
 }
 
 pub fn config_translators_paths(cfg: &mut ServiceConfig) {
+    cfg.service(create_translator);
+    cfg.service(merge_translators);
     cfg.service(add_translator);
     cfg.service(get_translators);
+    cfg.service(get_translator);
+    cfg.service(update_translator);
     cfg.service(delete_translator);
+    cfg.service(rename_translator);
+}
+
+pub fn config_calendar_paths(cfg: &mut ServiceConfig) {
+    cfg.service(get_instructor_calendar);
+    cfg.service(get_host_calendar);
+    cfg.service(get_full_calendar);
+}
+
+pub fn config_series_paths(cfg: &mut ServiceConfig) {
+    cfg.service(add_series);
+    cfg.service(get_series_list);
+    cfg.service(get_series);
+    cfg.service(edit_series);
+}
+
+pub fn config_stats_paths(cfg: &mut ServiceConfig) {
+    cfg.service(get_engagement_stats);
+}
+
+pub fn config_duplicates_paths(cfg: &mut ServiceConfig) {
+    cfg.service(get_duplicates);
+}
+
+pub fn config_trash_paths(cfg: &mut ServiceConfig) {
+    cfg.service(get_trash);
+    cfg.service(restore_from_trash);
 }
@@ -0,0 +1,389 @@
+use actix_web::{
+    get, patch, post,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{Engagement, FlyerStatus, HostStatus, Language, NewEngagement, Status};
+use crate::audit::{actor, diff, AuditEntry, Operation};
+use crate::conflicts::{
+    conflict_response, find_conflicts, reschedules, ConflictConfig, ConflictMode,
+};
+use crate::integrity::{unknown_reference, KnownNames};
+use crate::names::normalize_name;
+use crate::numbering::insert_numbered;
+use crate::types::{AuditRepo, HostRepo, InstructorRepo};
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct SeriesPart {
+    pub date: String,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct NewSeries {
+    pub instructor: String,
+    pub host: String,
+    pub language: Language,
+    pub title: String,
+    pub status: Status,
+    pub host_status: HostStatus,
+    pub flyer_status: FlyerStatus,
+    pub notes: String,
+    pub number: usize,
+    pub activity_type: String,
+    pub last_updated_by: String,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    pub parts: Vec<SeriesPart>,
+}
+
+impl NewSeries {
+    fn to_new_engagements(&self) -> Vec<NewEngagement> {
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| NewEngagement {
+                instructor: self.instructor.clone(),
+                host: self.host.clone(),
+                date: part.date.clone(),
+                language: self.language.clone(),
+                title: self.title.clone(),
+                part: index + 1,
+                num_parts: self.parts.len(),
+                status: self.status.clone(),
+                host_status: self.host_status.clone(),
+                flyer_status: self.flyer_status.clone(),
+                notes: self.notes.clone(),
+                number: self.number + index,
+                activity_type: self.activity_type.clone(),
+                last_updated_by: self.last_updated_by.clone(),
+                start_time: part.start_time.clone().or(self.start_time.clone()),
+                end_time: part.end_time.clone().or(self.end_time.clone()),
+                timezone: self.timezone.clone(),
+            })
+            .collect()
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct SeriesEdit {
+    pub instructor: Option<String>,
+    pub host: Option<String>,
+    pub language: Option<Language>,
+    // The version of every part as the client last read it, by engagement id
+    #[serde(default)]
+    pub versions: BTreeMap<Uuid, u64>,
+    pub last_updated_by: String,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct SeriesIssues {
+    pub missing: Vec<usize>,
+    pub duplicates: Vec<usize>,
+    pub inconsistent_num_parts: Vec<usize>,
+}
+
+impl SeriesIssues {
+    fn is_empty(&self) -> bool {
+        self.missing.is_empty()
+            && self.duplicates.is_empty()
+            && self.inconsistent_num_parts.is_empty()
+    }
+}
+
+pub fn series_issues(parts: &[&Engagement]) -> SeriesIssues {
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    for eng in parts {
+        *counts.entry(eng.part).or_default() += 1;
+    }
+
+    let num_parts: BTreeSet<usize> = parts.iter().map(|e| e.num_parts).collect();
+    let expected = num_parts.iter().max().copied().unwrap_or(0);
+
+    SeriesIssues {
+        missing: (1..=expected).filter(|p| !counts.contains_key(p)).collect(),
+        duplicates: counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(part, _)| part)
+            .collect(),
+        inconsistent_num_parts: if num_parts.len() > 1 {
+            num_parts.into_iter().collect()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+fn series_parts(repo: &HashSet<Engagement>, series_id: Uuid) -> Vec<&Engagement> {
+    let mut parts: Vec<&Engagement> = repo
+        .iter()
+        .filter(|e| e.series_id == Some(series_id))
+        .collect();
+    parts.sort_by(|a, b| a.part.cmp(&b.part).then_with(|| a.date.cmp(&b.date)));
+    parts
+}
+
+fn series_summary(series_id: Uuid, parts: &[&Engagement]) -> serde_json::Value {
+    let issues = series_issues(parts);
+    json!({
+        "series_id": series_id,
+        "title": parts.first().map(|e| e.title.clone()),
+        "valid": issues.is_empty(),
+        "issues": issues,
+        "parts": parts,
+    })
+}
+
+// Nothing is saved unless all parts are valid
+#[post("/series")]
+pub async fn add_series(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    body: Json<NewSeries>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if body.parts.is_empty() {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Validation failed",
+                "details": "A series needs at least one part"
+            })));
+    }
+
+    let new_engs = body.to_new_engagements();
+    for new_eng in &new_engs {
+        if let Err(validation_error) = new_eng.validate() {
+            return Ok(HttpResponse::BadRequest()
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Validation failed",
+                    "details": format!("Part {}: {}", new_eng.part, validation_error)
+                })));
+        }
+    }
+
+    let series_id = Uuid::new_v4();
+    let actor = actor(&req, Some(&body.last_updated_by));
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    // Every part shares the instructor and host, so checking one covers all
+    if let Err(reference_error) =
+        KnownNames::load(&instructors, &hosts)?.check(None, &new_engs[0].to_engagement())
+    {
+        return Ok(unknown_reference(reference_error));
+    }
+
+    let mut working_repo = repo_guard.clone();
+    let mut working_audit = Vec::new();
+    let mut conflicts = BTreeSet::new();
+    let mut ids = Vec::new();
+
+    for new_eng in &new_engs {
+        let mut eng = new_eng.to_engagement();
+        eng.series_id = Some(series_id);
+
+        conflicts.extend(find_conflicts(working_repo.iter(), &eng));
+        ids.push(eng.id);
+        insert_numbered(&mut working_repo, &mut working_audit, &actor, eng);
+    }
+
+    let conflicts: Vec<Uuid> = conflicts.into_iter().collect();
+    if !conflicts.is_empty() && conflict_config.mode == ConflictMode::Reject {
+        return Ok(conflict_response(&conflicts));
+    }
+
+    audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .extend(working_audit);
+    *repo_guard = working_repo;
+
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .json(json!({
+            "series_id": series_id,
+            "engagements": ids,
+            "conflicts": conflicts
+        })))
+}
+
+#[get("/series")]
+pub async fn get_series_list(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let series_ids: BTreeSet<Uuid> = repo_guard.iter().filter_map(|e| e.series_id).collect();
+    let series: Vec<serde_json::Value> = series_ids
+        .into_iter()
+        .map(|series_id| series_summary(series_id, &series_parts(&repo_guard, series_id)))
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(series))
+}
+
+#[get("/series/{id}")]
+pub async fn get_series(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let series_id = path.into_inner();
+
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let parts = series_parts(&repo_guard, series_id);
+    if parts.is_empty() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(series_summary(series_id, &parts)))
+}
+
+// Either all parts are updated or, on a rejected conflict, none are
+#[patch("/series/{id}")]
+#[allow(clippy::too_many_arguments)]
+pub async fn edit_series(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Json<SeriesEdit>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let series_id = path.into_inner();
+    let edit = body.into_inner();
+    let actor = actor(&req, Some(&edit.last_updated_by));
+    let last_updated_by = format!(
+        "{} {}",
+        edit.last_updated_by,
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let parts: Vec<Engagement> = series_parts(&repo_guard, series_id)
+        .into_iter()
+        .cloned()
+        .collect();
+    if parts.is_empty() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    if parts.iter().any(|e| !edit.versions.contains_key(&e.id)) {
+        return Ok(
+            HttpResponse::build(actix_web::http::StatusCode::PRECONDITION_REQUIRED)
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Precondition required",
+                    "details": "Send the version of every part of the series in versions"
+                })),
+        );
+    }
+    let stale = edit.versions.len() != parts.len()
+        || parts.iter().any(|e| edit.versions[&e.id] != e.version);
+    if stale {
+        return Ok(HttpResponse::PreconditionFailed()
+            .content_type("application/json")
+            .json(json!({
+                "error": "Version mismatch",
+                "details": "The series was modified by someone else",
+                "current": series_summary(series_id, &parts.iter().collect::<Vec<_>>())
+            })));
+    }
+    let known = KnownNames::load(&instructors, &hosts)?;
+
+    let mut working_repo = repo_guard.clone();
+    let mut entries = Vec::new();
+    let mut updated = Vec::new();
+
+    for current in &parts {
+        let mut eng = current.clone();
+        if let Some(instructor) = &edit.instructor {
+            eng.instructor = normalize_name(instructor);
+        }
+        if let Some(host) = &edit.host {
+            eng.host = normalize_name(host);
+        }
+        if let Some(language) = &edit.language {
+            eng.language = language.clone();
+        }
+        if diff(Some(current), Some(&eng)).is_empty() {
+            continue;
+        }
+        if let Err(reference_error) = known.check(Some(current), &eng) {
+            return Ok(unknown_reference(reference_error));
+        }
+        eng.last_updated_by = Some(last_updated_by.clone());
+        eng.version += 1;
+
+        entries.push(AuditEntry::new(
+            Operation::Update,
+            &actor,
+            Some(current),
+            Some(&eng),
+        ));
+        updated.push(eng.clone());
+        working_repo.replace(eng);
+    }
+
+    let conflicts: BTreeSet<Uuid> = updated
+        .iter()
+        .filter(|eng| {
+            parts
+                .iter()
+                .find(|current| current.id == eng.id)
+                .is_some_and(|current| reschedules(current, eng))
+        })
+        .flat_map(|eng| find_conflicts(working_repo.iter(), eng))
+        .collect();
+    let conflicts: Vec<Uuid> = conflicts.into_iter().collect();
+    if !conflicts.is_empty() && conflict_config.mode == ConflictMode::Reject {
+        return Ok(conflict_response(&conflicts));
+    }
+
+    audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?
+        .extend(entries);
+    *repo_guard = working_repo;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(json!({
+            "series_id": series_id,
+            "engagements": updated.iter().map(|e| e.id).collect::<Vec<Uuid>>(),
+            "conflicts": conflicts
+        })))
+}
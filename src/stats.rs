@@ -0,0 +1,123 @@
+use actix_web::{get, web::Data, HttpRequest, HttpResponse};
+use chrono::{Duration, NaiveDate, Utc};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::api::{invalid_query, Engagement, EngagementQuery, Status};
+
+const DEFAULT_UPCOMING_DAYS: i64 = 14;
+// Ten years; larger windows would overflow the date arithmetic
+const MAX_UPCOMING_DAYS: i64 = 3660;
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct StatsOptions {
+    pub days: Option<i64>,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct EngagementStats {
+    pub total: usize,
+    pub by_status: BTreeMap<String, usize>,
+    pub by_host_status: BTreeMap<String, usize>,
+    pub by_flyer_status: BTreeMap<String, usize>,
+    pub by_language: BTreeMap<String, usize>,
+    pub by_instructor: BTreeMap<String, usize>,
+    pub by_host: BTreeMap<String, usize>,
+    // YYYY-MM
+    pub by_month: BTreeMap<String, usize>,
+}
+
+fn label<T: std::fmt::Debug>(value: Option<&T>) -> String {
+    value
+        .map(|v| format!("{:?}", v))
+        .unwrap_or_else(|| "Unset".to_string())
+}
+
+fn month(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| date.format("%Y-%m").to_string())
+        .unwrap_or_else(|_| "Invalid date".to_string())
+}
+
+pub fn engagement_stats<'a>(
+    engagements: impl IntoIterator<Item = &'a Engagement>,
+) -> EngagementStats {
+    let mut stats = EngagementStats::default();
+    for eng in engagements {
+        stats.total += 1;
+        for (counts, key) in [
+            (&mut stats.by_status, label(Some(&eng.status))),
+            (&mut stats.by_host_status, label(eng.host_status.as_ref())),
+            (&mut stats.by_flyer_status, label(eng.flyer_status.as_ref())),
+            (&mut stats.by_language, label(Some(&eng.language))),
+            (&mut stats.by_instructor, eng.instructor.clone()),
+            (&mut stats.by_host, eng.host.clone()),
+            (&mut stats.by_month, month(&eng.date)),
+        ] {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    stats
+}
+
+#[get("/stats/engagements")]
+pub async fn get_engagement_stats(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    req: HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    let query = match EngagementQuery::from_query_string(req.query_string()) {
+        Ok(query) => query,
+        Err(e) => return Ok(invalid_query(e)),
+    };
+    let options: StatsOptions = match serde_html_form::from_str(req.query_string()) {
+        Ok(options) => options,
+        Err(e) => return Ok(invalid_query(format!("Invalid query string: {}", e))),
+    };
+    let days = options.days.unwrap_or(DEFAULT_UPCOMING_DAYS);
+    if !(0..=MAX_UPCOMING_DAYS).contains(&days) {
+        return Ok(invalid_query(format!(
+            "days must be between 0 and {}",
+            MAX_UPCOMING_DAYS
+        )));
+    }
+
+    let today = Utc::now().date_naive();
+    let horizon = today + Duration::days(days);
+
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let engagements: Vec<&Engagement> = repo_guard.iter().filter(|e| query.matches(e)).collect();
+    let dated = |eng: &Engagement| NaiveDate::parse_from_str(&eng.date, "%Y-%m-%d").ok();
+
+    let mut upcoming: Vec<&Engagement> = engagements
+        .iter()
+        .copied()
+        .filter(|e| e.status != Status::Rejected)
+        .filter(|e| dated(e).is_some_and(|date| date >= today && date <= horizon))
+        .collect();
+    upcoming.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+
+    let mut overdue: Vec<&Engagement> = engagements
+        .iter()
+        .copied()
+        .filter(|e| !matches!(e.status, Status::Complete | Status::Rejected))
+        .filter(|e| dated(e).is_some_and(|date| date < today))
+        .collect();
+    overdue.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(json!({
+            "counts": engagement_stats(engagements.iter().copied()),
+            "upcoming": {
+                "days": days,
+                "engagements": upcoming
+            },
+            "overdue": overdue
+        })))
+}
@@ -17,11 +17,22 @@ Stage 8: English Editing (Separate file assembly - Host and Interviewee)
 
 Stage 9: Final Editing (Bilingual Editor)
  */
-use actix_web::web::{Data, Json, Path};
-use actix_web::{delete, get, patch, post, HttpResponse};
+use actix_web::web::{Bytes, Data, Json, Path};
+use actix_web::{delete, get, patch, post, HttpRequest, HttpResponse};
 use chrono::NaiveDate;
 use serde_json::json;
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::audit::actor;
+use crate::merge_patch::{merge_patch, parse_merge_patch};
+use crate::names::normalize_name;
+use crate::preconditions::{check_if_match, etag, versioned_response};
+use crate::translators::{check_assignments, Translator};
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{TranslationIds, TranslatorRepo, TrashRepo};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub enum Stage {
@@ -63,6 +74,8 @@ pub struct Translation {
     pub due_date: String,
     pub file_url: String,
     pub last_update_by: String,
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl Translation {
@@ -82,11 +95,12 @@ impl Translation {
             id: self.id,
             name: ammonia::clean(&self.name),
             stage: self.stage.clone(),
-            translators: self.translators.clone(), // sanitized in translators.rs
+            translators: self.translators.iter().map(|n| normalize_name(n)).collect(),
             due_date: ammonia::clean(&self.due_date),
             file_url: self.file_url.clone(),
             // TODO add logic to limit URLs to s3 links inside a specific bucket
             last_update_by: ammonia::clean(&self.last_update_by),
+            version: self.version,
         }
     }
 }
@@ -99,9 +113,58 @@ pub struct Query {
     pub translators: Option<Vec<String>>,
 }
 
+fn invalid_assignment(details: String) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Invalid assignment",
+            "details": details
+        }))
+}
+
+fn allocate_id(next_id: &mut u32) -> u32 {
+    let id = *next_id;
+    *next_id += 1;
+    id
+}
+
+// Translations created before the allocator existed were numbered len() + 1,
+// which repeats after a delete; the first one with an id keeps it
+pub fn repair_ids(
+    translations: &mut [Translation],
+    trash: &mut [TrashEntry],
+    next_id: &mut u32,
+) -> Vec<(u32, u32)> {
+    let trashed = trash
+        .iter_mut()
+        .filter_map(|entry| match &mut entry.record {
+            TrashedRecord::Translation(translation) => Some(translation),
+            TrashedRecord::Engagement(_) => None,
+        });
+    let mut all: Vec<&mut Translation> = translations.iter_mut().chain(trashed).collect();
+
+    if let Some(max) = all.iter().map(|t| t.id).max() {
+        *next_id = (*next_id).max(max + 1);
+    }
+
+    let mut seen = HashSet::new();
+    let mut rekeyed = Vec::new();
+    for translation in all.iter_mut() {
+        if !seen.insert(translation.id) {
+            let new_id = allocate_id(next_id);
+            rekeyed.push((translation.id, new_id));
+            translation.id = new_id;
+        }
+    }
+
+    rekeyed
+}
+
 #[post("/translations")]
 pub async fn create_translation(
     repo: Data<Arc<Mutex<Vec<Translation>>>>,
+    translators: Data<TranslatorRepo>,
+    ids: Data<TranslationIds>,
     body: Json<Translation>,
 ) -> Result<HttpResponse, actix_web::Error> {
     if let Err(validation_error) = body.validate() {
@@ -117,13 +180,32 @@ pub async fn create_translation(
     let mut repo_guard = repo
         .lock()
         .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let translators_guard = translators.lock().map_err(|_| {
+        actix_web::error::ErrorInternalServerError("Failed to acquire translator lock")
+    })?;
 
-    let count: u32 = repo_guard.len() as u32;
-    translation.id = count + 1;
+    let assigned: Vec<&String> = translation.translators.iter().collect();
+    if let Err(assignment_error) = check_assignments(
+        &translators_guard,
+        &translation.stage,
+        &translation.translators,
+        &assigned,
+    ) {
+        return Ok(invalid_assignment(assignment_error));
+    }
 
+    let mut ids_guard = ids
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire id lock"))?;
+    translation.id = allocate_id(&mut ids_guard);
+    translation.version = 1;
+
+    let id = translation.id;
     repo_guard.push(translation);
 
-    Ok(HttpResponse::Created().finish())
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .json(json!({ "id": id })))
 }
 
 #[get("/translations")]
@@ -138,15 +220,16 @@ pub async fn get_translations(
     let mut translations: Vec<Translation> = repo_guard
         .iter()
         .filter(|x| {
-            body.id.map_or(true, |q_id| x.id == q_id)
+            body.id.is_none_or(|q_id| x.id == q_id)
                 && body
                     .name
                     .as_ref()
-                    .map_or(true, |q_name| x.name.contains(q_name))
-                && body.stage.as_ref().map_or(true, |q_stage| {
-                    matches!(q_stage, Stage::Any) || &x.stage == q_stage
-                })
-                && body.translators.as_ref().map_or(true, |q_translators| {
+                    .is_none_or(|q_name| x.name.contains(q_name))
+                && body
+                    .stage
+                    .as_ref()
+                    .is_none_or(|q_stage| matches!(q_stage, Stage::Any) || &x.stage == q_stage)
+                && body.translators.as_ref().is_none_or(|q_translators| {
                     q_translators.iter().any(|t| x.translators.contains(t))
                 })
         })
@@ -159,53 +242,179 @@ pub async fn get_translations(
         .json(translations))
 }
 
-#[patch("/translations")]
-pub async fn update_translation(
-    // Client is expected to send all updates in payload, payload should be a complete translation object with the last_updated_by reflecting the editor
+#[get("/translations/{id}")]
+pub async fn get_translation(
     repo: Data<Arc<Mutex<Vec<Translation>>>>,
-    body: Json<Translation>,
+    req: HttpRequest,
+    path: Path<u32>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    if let Err(validation_error) = body.validate() {
-        return Ok(HttpResponse::BadRequest()
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    match repo_guard.iter().find(|x| x.id == *path) {
+        Some(translation) => Ok(versioned_response(&req, translation, translation.version)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+// Shared by PATCH /translations and the merge patch PATCH /translations/{id}
+fn update_translation_in(
+    repo: &mut [Translation],
+    translators: &HashSet<Translator>,
+    req: &HttpRequest,
+    edited: Translation,
+) -> HttpResponse {
+    if let Err(validation_error) = edited.validate() {
+        return HttpResponse::BadRequest()
             .content_type("application/json")
             .json(json!({
                 "error": "Validation failed",
                 "details": validation_error
-            })));
+            }));
     }
-    let edit = body.into_inner().clean();
+    let edit = edited.clean();
 
-    let mut repo_guard = repo
-        .lock()
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
-    if let Some(target) = repo_guard.iter_mut().find(|x| x.id == edit.id) {
-        // would a deletion and insertion eb more appropriate here? The payload describes a complete object 
+    if let Some(target) = repo.iter_mut().find(|x| x.id == edit.id) {
+        if let Err(response) = check_if_match(req, target, target.version) {
+            return response;
+        }
+
+        // Only re-check what the edit changes, so unrelated edits to older
+        // translations are not blocked by assignments made before profiles
+        let newly_assigned: Vec<&String> = edit
+            .translators
+            .iter()
+            .filter(|name| !target.translators.contains(name))
+            .collect();
+        let to_check: Vec<String> = if target.stage != edit.stage {
+            edit.translators.clone()
+        } else {
+            newly_assigned.iter().map(|name| name.to_string()).collect()
+        };
+        if let Err(assignment_error) =
+            check_assignments(translators, &edit.stage, &to_check, &newly_assigned)
+        {
+            return invalid_assignment(assignment_error);
+        }
+
+        // would a deletion and insertion eb more appropriate here? The payload describes a complete object
         target.name = edit.name;
         target.stage = edit.stage;
         target.translators = edit.translators;
         target.due_date = edit.due_date;
         target.file_url = edit.file_url;
         target.last_update_by = edit.last_update_by;
+        target.version += 1;
+
+        HttpResponse::Ok()
+            .insert_header(("ETag", etag(target.version).to_string()))
+            .finish()
     } else {
+        HttpResponse::NotFound().finish()
+    }
+}
+
+#[patch("/translations")]
+pub async fn update_translation(
+    // Client is expected to send all updates in payload, payload should be a complete translation object with the last_updated_by reflecting the editor
+    repo: Data<Arc<Mutex<Vec<Translation>>>>,
+    translators: Data<TranslatorRepo>,
+    req: HttpRequest,
+    body: Json<Translation>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let translators_guard = translators.lock().map_err(|_| {
+        actix_web::error::ErrorInternalServerError("Failed to acquire translator lock")
+    })?;
+
+    Ok(update_translation_in(
+        &mut repo_guard,
+        &translators_guard,
+        &req,
+        body.into_inner(),
+    ))
+}
+
+#[patch("/translations/{id}")]
+pub async fn patch_translation(
+    repo: Data<Arc<Mutex<Vec<Translation>>>>,
+    translators: Data<TranslatorRepo>,
+    req: HttpRequest,
+    path: Path<u32>,
+    body: Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    let patch = match parse_merge_patch(&req, &body) {
+        Ok(patch) => patch,
+        Err(response) => return Ok(response),
+    };
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let translators_guard = translators.lock().map_err(|_| {
+        actix_web::error::ErrorInternalServerError("Failed to acquire translator lock")
+    })?;
+
+    let Some(current) = repo_guard.iter().find(|x| x.id == target_id) else {
         return Ok(HttpResponse::NotFound().finish());
+    };
+    let mut merged =
+        serde_json::to_value(current).map_err(actix_web::error::ErrorInternalServerError)?;
+    merge_patch(&mut merged, &patch);
+
+    let mut edited: Translation = match serde_json::from_value(merged) {
+        Ok(edited) => edited,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .content_type("application/json")
+                .json(json!({
+                    "error": "Validation failed",
+                    "details": e.to_string()
+                })))
+        }
+    };
+    edited.id = target_id;
+    // The stored value names the previous editor, so it is never reused
+    if patch.get("last_update_by").is_none() {
+        edited.last_update_by = actor(&req, None);
     }
 
-    Ok(HttpResponse::Ok().finish())
+    Ok(update_translation_in(
+        &mut repo_guard,
+        &translators_guard,
+        &req,
+        edited,
+    ))
 }
 
 #[delete("/translations/{id}")]
 pub async fn delete_translation(
     repo: Data<Arc<Mutex<Vec<Translation>>>>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
     path: Path<u32>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let mut repo_guard = repo
         .lock()
         .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
-    if let Some(position) = repo_guard.iter().position(|x| x.id == path.clone()) {
-        repo_guard.remove(position);
-    } else {
+    let mut trash_guard = trash
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire trash lock"))?;
+
+    let Some(position) = repo_guard.iter().position(|x| x.id == *path) else {
         return Ok(HttpResponse::NotFound().finish());
-    }
+    };
+    let translation = repo_guard.remove(position);
 
-    Ok(HttpResponse::Ok().finish())
+    let entry = TrashEntry::new(TrashedRecord::Translation(translation), &actor(&req, None));
+    let trash_id = entry.id;
+    trash_guard.push(entry);
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(json!({ "trash_id": trash_id })))
 }
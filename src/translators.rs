@@ -1,21 +1,329 @@
-use crate::TranslatorRepo;
+use crate::api::Language;
+use crate::contact::{clean_optional, validate_email, validate_phone};
+use crate::names::{normalize_name, search, SearchQuery};
+use crate::preconditions::versioned_response;
+use crate::profiles::{
+    add_profile_by_name, create_profile, find_profile, merge_profiles, rename_profile,
+    update_profile, validation_failed, MergeRequest, Profile, RenameRequest, TranslationRefs,
+};
+use crate::translations::{Stage, Translation};
+use crate::trash::{TrashEntry, TrashedRecord};
+use crate::types::{TranslatorRepo, TrashRepo};
 use actix_web::{
-    delete, get, post,
-    web::{Data, Path},
-    HttpResponse,
+    delete, get, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
 };
+use uuid::Uuid;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct LanguagePair {
+    pub source: Language,
+    pub target: Language,
+}
+
+fn default_available() -> bool {
+    true
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Translator {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub language_pairs: Vec<LanguagePair>,
+    #[serde(default)]
+    pub stages: Vec<Stage>,
+    // Unavailable translators keep their assignments but cannot take new ones
+    #[serde(default = "default_available")]
+    pub available: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours_per_week: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl Translator {
+    pub fn is_qualified_for(&self, stage: &Stage) -> bool {
+        self.stages.contains(&Stage::Any) || self.stages.contains(stage)
+    }
+}
+
+impl Profile for Translator {
+    const KIND: &'static str = "translator";
+    const REFERRERS: &'static str = "translations";
+
+    // Name-only translators were assignable to any stage, so they stay that way
+    fn from_name(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            email: None,
+            phone: None,
+            language_pairs: Vec::new(),
+            stages: vec![Stage::Any],
+            available: true,
+            hours_per_week: None,
+            notes: None,
+            version: 1,
+        }
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    // Pairs and stages are combined so the merged translator stays qualified
+    // for everything either one was assigned to
+    fn absorb(&mut self, other: &Translator) {
+        self.email = self.email.take().or_else(|| other.email.clone());
+        self.phone = self.phone.take().or_else(|| other.phone.clone());
+        self.hours_per_week = self.hours_per_week.or(other.hours_per_week);
+        self.notes = self.notes.take().or_else(|| other.notes.clone());
+        for pair in &other.language_pairs {
+            if !self.language_pairs.contains(pair) {
+                self.language_pairs.push(pair.clone());
+            }
+        }
+        for stage in &other.stages {
+            if !self.stages.contains(stage) {
+                self.stages.push(stage.clone());
+            }
+        }
+    }
+}
+
+impl std::hash::Hash for Translator {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialEq for Translator {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Translator {}
+
+// Only newly assigned translators must be available, so existing assignments
+// survive a translator's leave
+pub fn check_assignments(
+    translators: &HashSet<Translator>,
+    stage: &Stage,
+    assigned: &[String],
+    newly_assigned: &[&String],
+) -> Result<(), String> {
+    for name in assigned {
+        let Some(translator) = translators.iter().find(|t| t.name == *name) else {
+            return Err(format!("Unknown translator: {}", name));
+        };
+        if !translator.is_qualified_for(stage) {
+            return Err(format!(
+                "{} is not qualified for stage {:?}; qualified for {:?}",
+                name, stage, translator.stages
+            ));
+        }
+        if !translator.available && newly_assigned.contains(&name) {
+            return Err(format!("{} is not available for new assignments", name));
+        }
+    }
+
+    Ok(())
+}
+
+fn replace_assignee(translators: &mut Vec<String>, from: &str, to: &str) {
+    let mut replaced: Vec<String> = Vec::new();
+    for name in translators.iter() {
+        let name = if name == from { to } else { name };
+        if !replaced.iter().any(|existing| existing == name) {
+            replaced.push(name.to_string());
+        }
+    }
+    *translators = replaced;
+}
+
+// Trashed translations follow too, so they come back assigned to the current
+// name
+pub fn reassign_translations(
+    translations: &mut [Translation],
+    trash: &mut [TrashEntry],
+    actor: &str,
+    from: &str,
+    to: &str,
+) -> Vec<u32> {
+    let assigned = |t: &Translation| t.translators.iter().any(|name| name == from);
+
+    let mut ids = Vec::new();
+    for translation in translations.iter_mut().filter(|t| assigned(t)) {
+        replace_assignee(&mut translation.translators, from, to);
+        translation.last_update_by = actor.to_string();
+        translation.version += 1;
+        ids.push(translation.id);
+    }
+    for entry in trash.iter_mut() {
+        if let TrashedRecord::Translation(translation) = &mut entry.record {
+            if assigned(translation) {
+                replace_assignee(&mut translation.translators, from, to);
+            }
+        }
+    }
+    ids.sort();
+    ids
+}
+
+pub fn normalize_assignees(translations: &mut [Translation], trash: &mut [TrashEntry]) {
+    let normalize = |translation: &mut Translation| {
+        let mut translators: Vec<String> = Vec::new();
+        for name in translation.translators.iter().map(|n| normalize_name(n)) {
+            if !translators.contains(&name) {
+                translators.push(name);
+            }
+        }
+        let changed = translators != translation.translators;
+        translation.translators = translators;
+        changed
+    };
+
+    for translation in translations.iter_mut() {
+        if normalize(translation) {
+            translation.version += 1;
+        }
+    }
+    for entry in trash.iter_mut() {
+        if let TrashedRecord::Translation(translation) = &mut entry.record {
+            normalize(translation);
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct TranslatorInput {
+    pub name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub language_pairs: Vec<LanguagePair>,
+    #[serde(default)]
+    pub stages: Vec<Stage>,
+    #[serde(default = "default_available")]
+    pub available: bool,
+    #[serde(default)]
+    pub hours_per_week: Option<u32>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl TranslatorInput {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name must not be empty".to_string());
+        }
+        if let Some(email) = self.email.as_deref().filter(|e| !e.trim().is_empty()) {
+            validate_email(email.trim())?;
+        }
+        if let Some(phone) = self.phone.as_deref().filter(|p| !p.trim().is_empty()) {
+            validate_phone(phone.trim())?;
+        }
+        if let Some(pair) = self
+            .language_pairs
+            .iter()
+            .find(|p| p.source == p.target || p.source == Language::Any)
+        {
+            return Err(format!(
+                "Invalid language pair: {:?} to {:?}",
+                pair.source, pair.target
+            ));
+        }
+        if self.hours_per_week.is_some_and(|hours| hours > 168) {
+            return Err("Hours per week cannot exceed 168".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn to_translator(&self, id: Uuid, version: u64) -> Translator {
+        let mut language_pairs: Vec<LanguagePair> = Vec::new();
+        for pair in &self.language_pairs {
+            if !language_pairs.contains(pair) {
+                language_pairs.push(pair.clone());
+            }
+        }
+        let mut stages: Vec<Stage> = Vec::new();
+        for stage in &self.stages {
+            if !stages.contains(stage) {
+                stages.push(stage.clone());
+            }
+        }
+
+        Translator {
+            id,
+            name: normalize_name(&self.name),
+            email: clean_optional(self.email.as_deref()),
+            phone: clean_optional(self.phone.as_deref()),
+            language_pairs,
+            stages,
+            available: self.available,
+            hours_per_week: self.hours_per_week,
+            notes: clean_optional(self.notes.as_deref()),
+            version,
+        }
+    }
+}
+
+#[post("/translators")]
+pub async fn create_translator(
+    repo: Data<TranslatorRepo>,
+    body: Json<TranslatorInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+    let translator = body.to_translator(Uuid::new_v4(), 1);
+
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(create_profile(&mut repo_guard, translator))
+}
+
 #[post("/translators/{new}")]
 pub async fn add_translator(
     repo: Data<TranslatorRepo>,
     new: Path<String>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&new);
-
     match repo.lock() {
-        Ok(mut repo) => {
-            repo.insert(sanitized);
-            Ok(HttpResponse::Created().finish())
-        }
+        Ok(mut repo) => Ok(add_profile_by_name(&mut repo, &new)),
         Err(_) => Err(actix_web::error::ErrorInternalServerError(
             "Failed to acquire repo lock",
         )),
@@ -23,11 +331,13 @@ pub async fn add_translator(
 }
 
 #[get("/translators")]
-pub async fn get_translators(repo: Data<TranslatorRepo>) -> Result<HttpResponse, actix_web::Error> {
+pub async fn get_translators(
+    repo: Data<TranslatorRepo>,
+    query: Query<SearchQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
     match repo.lock() {
         Ok(repo) => {
-            let mut translators: Vec<String> = repo.iter().cloned().collect();
-            translators.sort();
+            let translators = search(repo.iter().cloned().collect(), |t| &t.name, &query);
 
             Ok(HttpResponse::Ok()
                 .content_type("application/json; charset=utf-8")
@@ -39,17 +349,58 @@ pub async fn get_translators(repo: Data<TranslatorRepo>) -> Result<HttpResponse,
     }
 }
 
+#[get("/translators/{id}")]
+pub async fn get_translator(
+    repo: Data<TranslatorRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    match repo_guard.iter().find(|t| t.id == *path) {
+        Some(translator) => Ok(versioned_response(&req, translator, translator.version)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[patch("/translators/{id}")]
+pub async fn update_translator(
+    repo: Data<TranslatorRepo>,
+    translations: Data<Arc<Mutex<Vec<Translation>>>>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+    body: Json<TranslatorInput>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    if let Err(validation_error) = body.validate() {
+        return Ok(validation_failed(validation_error));
+    }
+    let mut refs = TranslationRefs::lock(&translations, &trash, &req)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(update_profile(
+        &mut repo_guard,
+        &req,
+        target_id,
+        |version| body.to_translator(target_id, version),
+        |from, to| refs.reassign(from, to),
+    ))
+}
+
 #[delete("/translators/{i}")]
 pub async fn delete_translator(
     repo: Data<TranslatorRepo>,
     i: Path<String>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let sanitized = ammonia::clean(&i);
-
     match repo.lock() {
         Ok(mut repo) => {
-            if repo.contains(&sanitized) {
-                repo.remove(&sanitized);
+            if let Some(target) = find_profile(&repo, &i) {
+                repo.remove(&target);
                 Ok(HttpResponse::Ok().finish())
             } else {
                 Ok(HttpResponse::NotFound().finish())
@@ -60,3 +411,40 @@ pub async fn delete_translator(
         )),
     }
 }
+
+#[post("/translators/{i}/rename")]
+pub async fn rename_translator(
+    repo: Data<TranslatorRepo>,
+    translations: Data<Arc<Mutex<Vec<Translation>>>>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    i: Path<String>,
+    body: Json<RenameRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = TranslationRefs::lock(&translations, &trash, &req)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(rename_profile(&mut repo_guard, &i, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
+}
+
+#[post("/translators/merge")]
+pub async fn merge_translators(
+    repo: Data<TranslatorRepo>,
+    translations: Data<Arc<Mutex<Vec<Translation>>>>,
+    trash: Data<TrashRepo>,
+    req: HttpRequest,
+    body: Json<MergeRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut refs = TranslationRefs::lock(&translations, &trash, &req)?;
+    let mut repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    Ok(merge_profiles(&mut repo_guard, &body, |from, to| {
+        refs.reassign(from, to)
+    }))
+}
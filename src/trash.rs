@@ -0,0 +1,218 @@
+use actix_web::{
+    get, post,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use tokio::time::interval;
+use uuid::Uuid;
+
+use crate::api::Engagement;
+use crate::audit::actor;
+use crate::conflicts::{
+    conflict_response, find_conflicts, with_conflict_warning, ConflictConfig, ConflictMode,
+};
+use crate::integrity::{unknown_reference, KnownNames};
+use crate::numbering::restore_numbered;
+use crate::preconditions::etag;
+use crate::translations::Translation;
+use crate::types::{AuditRepo, HostRepo, InstructorRepo, TrashRepo};
+
+const PURGE_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Clone, Debug)]
+pub struct TrashConfig {
+    pub retention_days: i64,
+}
+
+impl TrashConfig {
+    pub fn from_env() -> Self {
+        Self {
+            retention_days: std::env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+        }
+    }
+
+    pub fn purge_at(&self, entry: &TrashEntry) -> DateTime<Utc> {
+        entry.deleted_at + Duration::days(self.retention_days)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub enum TrashedRecord {
+    Engagement(Engagement),
+    Translation(Translation),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct TrashEntry {
+    pub id: Uuid,
+    pub deleted_by: String,
+    pub deleted_at: DateTime<Utc>,
+    pub record: TrashedRecord,
+}
+
+impl TrashEntry {
+    pub fn new(record: TrashedRecord, deleted_by: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            deleted_by: deleted_by.to_string(),
+            deleted_at: Utc::now(),
+            record,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct TrashListing<'a> {
+    #[serde(flatten)]
+    entry: &'a TrashEntry,
+    purge_at: DateTime<Utc>,
+}
+
+pub fn purge_expired(trash: &mut Vec<TrashEntry>, config: &TrashConfig) -> usize {
+    let now = Utc::now();
+    let before = trash.len();
+    trash.retain(|entry| config.purge_at(entry) > now);
+    before - trash.len()
+}
+
+pub fn start_purge_task(trash: TrashRepo, config: TrashConfig) {
+    log::info!(
+        "Starting trash purge task with retention of {} days",
+        config.retention_days
+    );
+
+    tokio::spawn(async move {
+        let mut interval = interval(tokio::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match trash.lock() {
+                Ok(mut trash) => {
+                    let purged = purge_expired(&mut trash, &config);
+                    if purged > 0 {
+                        log::info!("Purged {} expired records from trash", purged);
+                    }
+                }
+                Err(e) => log::error!("Unable to lock TrashRepo for purge: {}", e),
+            }
+        }
+    });
+}
+
+#[get("/trash")]
+pub async fn get_trash(
+    trash: Data<TrashRepo>,
+    config: Data<TrashConfig>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let trash_guard = trash
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire trash lock"))?;
+
+    let mut listing: Vec<TrashListing> = trash_guard
+        .iter()
+        .map(|entry| TrashListing {
+            entry,
+            purge_at: config.purge_at(entry),
+        })
+        .collect();
+    listing.sort_by_key(|listing| std::cmp::Reverse(listing.entry.deleted_at));
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(listing))
+}
+
+fn id_taken(kind: &str) -> HttpResponse {
+    HttpResponse::Conflict()
+        .content_type("application/json")
+        .json(json!({
+            "error": "Restore failed",
+            "details": format!("A {} with this id already exists", kind)
+        }))
+}
+
+// Engagements return to their old number and the ones after it move up
+#[post("/trash/{id}/restore")]
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_from_trash(
+    engagements: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    audit: Data<AuditRepo>,
+    translations: Data<Arc<Mutex<Vec<Translation>>>>,
+    trash: Data<TrashRepo>,
+    instructors: Data<InstructorRepo>,
+    hosts: Data<HostRepo>,
+    conflict_config: Data<ConflictConfig>,
+    req: HttpRequest,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+    let actor = actor(&req, None);
+
+    let mut eng_guard = engagements
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let mut audit_guard = audit
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire audit lock"))?;
+    let known = KnownNames::load(&instructors, &hosts)?;
+    let mut translations_guard = translations
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+    let mut trash_guard = trash
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire trash lock"))?;
+
+    let Some(position) = trash_guard.iter().position(|entry| entry.id == target_id) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let response = match &trash_guard[position].record {
+        TrashedRecord::Engagement(eng) => {
+            if eng_guard.iter().any(|e| e.id == eng.id) {
+                return Ok(id_taken("engagement"));
+            }
+            // The instructor or host may have been deleted since
+            if let Err(reference_error) = known.check(None, eng) {
+                return Ok(unknown_reference(reference_error));
+            }
+
+            let mut eng = eng.clone();
+            eng.version += 1;
+
+            let conflicts = find_conflicts(eng_guard.iter(), &eng);
+            if !conflicts.is_empty() && conflict_config.mode == ConflictMode::Reject {
+                return Ok(conflict_response(&conflicts));
+            }
+
+            let mut response = HttpResponse::Ok();
+            response.insert_header(("ETag", etag(eng.version).to_string()));
+            restore_numbered(&mut eng_guard, &mut audit_guard, &actor, eng);
+            with_conflict_warning(response, &conflicts)
+        }
+        TrashedRecord::Translation(translation) => {
+            if translations_guard.iter().any(|t| t.id == translation.id) {
+                return Ok(id_taken("translation"));
+            }
+
+            let mut translation = translation.clone();
+            translation.version += 1;
+
+            let response = HttpResponse::Ok()
+                .insert_header(("ETag", etag(translation.version).to_string()))
+                .finish();
+            translations_guard.push(translation);
+            response
+        }
+    };
+
+    trash_guard.remove(position);
+    Ok(response)
+}
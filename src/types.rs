@@ -3,14 +3,30 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use crate::audit::AuditEntry;
+use crate::hosts::Host;
+use crate::instructors::Instructor;
+use crate::translators::Translator;
+use crate::trash::TrashEntry;
+
+#[derive(Clone)]
+pub struct InstructorRepo(pub Arc<Mutex<HashSet<Instructor>>>);
+
+#[derive(Clone)]
+pub struct HostRepo(pub Arc<Mutex<HashSet<Host>>>);
+
 #[derive(Clone)]
-pub struct InstructorRepo(pub Arc<Mutex<HashSet<String>>>);
+pub struct TranslatorRepo(pub Arc<Mutex<HashSet<Translator>>>);
 
 #[derive(Clone)]
-pub struct HostRepo(pub Arc<Mutex<HashSet<String>>>);
+pub struct AuditRepo(pub Arc<Mutex<Vec<AuditEntry>>>);
 
 #[derive(Clone)]
-pub struct TranslatorRepo(pub Arc<Mutex<HashSet<String>>>);
+pub struct TrashRepo(pub Arc<Mutex<Vec<TrashEntry>>>);
+
+// Only ever grows, so a deleted translation's id is never reused
+#[derive(Clone)]
+pub struct TranslationIds(pub Arc<Mutex<u32>>);
 
 impl InstructorRepo {
     pub fn new() -> Self {
@@ -20,8 +36,8 @@ impl InstructorRepo {
     pub fn lock(
         &self,
     ) -> Result<
-        std::sync::MutexGuard<'_, HashSet<String>>,
-        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<String>>>,
+        std::sync::MutexGuard<'_, HashSet<Instructor>>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<Instructor>>>,
     > {
         self.0.lock()
     }
@@ -35,8 +51,8 @@ impl HostRepo {
     pub fn lock(
         &self,
     ) -> Result<
-        std::sync::MutexGuard<'_, HashSet<String>>,
-        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<String>>>,
+        std::sync::MutexGuard<'_, HashSet<Host>>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<Host>>>,
     > {
         self.0.lock()
     }
@@ -50,8 +66,53 @@ impl TranslatorRepo {
     pub fn lock(
         &self,
     ) -> Result<
-        std::sync::MutexGuard<'_, HashSet<String>>,
-        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<String>>>,
+        std::sync::MutexGuard<'_, HashSet<Translator>>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, HashSet<Translator>>>,
+    > {
+        self.0.lock()
+    }
+}
+
+impl AuditRepo {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    pub fn lock(
+        &self,
+    ) -> Result<
+        std::sync::MutexGuard<'_, Vec<AuditEntry>>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, Vec<AuditEntry>>>,
+    > {
+        self.0.lock()
+    }
+}
+
+impl TrashRepo {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    pub fn lock(
+        &self,
+    ) -> Result<
+        std::sync::MutexGuard<'_, Vec<TrashEntry>>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, Vec<TrashEntry>>>,
+    > {
+        self.0.lock()
+    }
+}
+
+impl TranslationIds {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(1)))
+    }
+
+    pub fn lock(
+        &self,
+    ) -> Result<
+        std::sync::MutexGuard<'_, u32>,
+        std::sync::PoisonError<std::sync::MutexGuard<'_, u32>>,
     > {
         self.0.lock()
     }
@@ -0,0 +1,204 @@
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpResponse,
+};
+use chrono::{NaiveDate, Utc};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::api::{Engagement, FlyerStatus, HostStatus, Status};
+
+impl Status {
+    // Complete is final
+    pub fn next(&self) -> Vec<Status> {
+        match self {
+            Status::Planning => vec![Status::Invited, Status::Rejected],
+            Status::Invited => vec![Status::Planning, Status::Confirmed, Status::Rejected],
+            Status::Confirmed => vec![Status::Invited, Status::Rejected, Status::Complete],
+            Status::Rejected => vec![Status::Planning],
+            Status::Complete => vec![],
+        }
+    }
+}
+
+impl HostStatus {
+    pub fn next(&self) -> Vec<HostStatus> {
+        match self {
+            HostStatus::Planning => vec![HostStatus::Invited, HostStatus::Rejected],
+            HostStatus::Invited => vec![
+                HostStatus::Planning,
+                HostStatus::Confirmed,
+                HostStatus::Rejected,
+            ],
+            HostStatus::Confirmed => vec![HostStatus::Invited, HostStatus::Rejected],
+            HostStatus::Rejected => vec![HostStatus::Planning],
+        }
+    }
+}
+
+impl FlyerStatus {
+    pub fn next(&self) -> Vec<FlyerStatus> {
+        match self {
+            FlyerStatus::Pending => vec![FlyerStatus::Sent],
+            FlyerStatus::Sent => vec![FlyerStatus::Pending, FlyerStatus::Complete],
+            FlyerStatus::Complete => vec![],
+        }
+    }
+}
+
+fn check_step<T: PartialEq + std::fmt::Debug>(
+    field: &str,
+    from: &T,
+    to: &T,
+    next: Vec<T>,
+) -> Result<(), String> {
+    if from == to || next.contains(to) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} cannot change from {:?} to {:?}; allowed: {:?}",
+            field, from, to, next
+        ))
+    }
+}
+
+// Older engagements may not have this status. A set one cannot be cleared;
+// an unset one may only become initial or a state reachable from it
+fn check_optional_step<T: PartialEq + std::fmt::Debug>(
+    field: &str,
+    from: Option<&T>,
+    to: Option<&T>,
+    initial: &T,
+    next: impl Fn(&T) -> Vec<T>,
+) -> Result<(), String> {
+    match (from, to) {
+        (Some(from), Some(to)) => check_step(field, from, to, next(from)),
+        (Some(from), None) => Err(format!("{} cannot be cleared once set ({:?})", field, from)),
+        (None, Some(to)) => check_step(field, initial, to, next(initial)),
+        (None, None) => Ok(()),
+    }
+}
+
+pub fn check_consistency(
+    status: &Status,
+    host_status: Option<&HostStatus>,
+    date: &str,
+) -> Result<(), String> {
+    let host_confirmed = host_status == Some(&HostStatus::Confirmed);
+
+    match status {
+        Status::Confirmed if !host_confirmed => {
+            Err("Status Confirmed requires host status Confirmed".to_string())
+        }
+        Status::Complete if !host_confirmed => {
+            Err("Status Complete requires host status Confirmed".to_string())
+        }
+        Status::Complete if !has_taken_place(date) => Err(format!(
+            "Status Complete is only allowed after the engagement date ({})",
+            date
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn has_taken_place(date: &str) -> bool {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok_and(|date| date <= Utc::now().date_naive())
+}
+
+pub fn check_transition(before: &Engagement, after: &Engagement) -> Result<(), String> {
+    check_step(
+        "Status",
+        &before.status,
+        &after.status,
+        before.status.next(),
+    )?;
+
+    check_optional_step(
+        "Host status",
+        before.host_status.as_ref(),
+        after.host_status.as_ref(),
+        &HostStatus::Planning,
+        HostStatus::next,
+    )?;
+
+    check_optional_step(
+        "Flyer status",
+        before.flyer_status.as_ref(),
+        after.flyer_status.as_ref(),
+        &FlyerStatus::Pending,
+        FlyerStatus::next,
+    )?;
+
+    // Only edits to the fields involved are held to the cross-field rules, so
+    // engagements that predate them stay editable
+    if before.status != after.status
+        || before.host_status != after.host_status
+        || before.date != after.date
+    {
+        check_consistency(&after.status, after.host_status.as_ref(), &after.date)?;
+    }
+
+    Ok(())
+}
+
+// The states check_optional_step accepts next
+fn optional_next<T: Clone>(current: Option<&T>, initial: T, next: impl Fn(&T) -> Vec<T>) -> Vec<T> {
+    match current {
+        Some(current) => next(current),
+        None => {
+            let mut states = vec![initial.clone()];
+            states.extend(next(&initial));
+            states
+        }
+    }
+}
+
+#[get("/engs/{id}/transitions")]
+pub async fn get_transitions(
+    repo: Data<Arc<Mutex<HashSet<Engagement>>>>,
+    path: Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let target_id = path.into_inner();
+
+    let repo_guard = repo
+        .lock()
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to acquire repo lock"))?;
+
+    let Some(eng) = repo_guard.iter().find(|e| e.id == target_id) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    // Only offer statuses whose cross-field rules already hold
+    let status: Vec<Status> = eng
+        .status
+        .next()
+        .into_iter()
+        .filter(|s| check_consistency(s, eng.host_status.as_ref(), &eng.date).is_ok())
+        .collect();
+    let host_status: Vec<HostStatus> = optional_next(
+        eng.host_status.as_ref(),
+        HostStatus::Planning,
+        HostStatus::next,
+    )
+    .into_iter()
+    .filter(|h| check_consistency(&eng.status, Some(h), &eng.date).is_ok())
+    .collect();
+    let flyer_status = optional_next(
+        eng.flyer_status.as_ref(),
+        FlyerStatus::Pending,
+        FlyerStatus::next,
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(json!({
+            "status": status,
+            "host_status": host_status,
+            "flyer_status": flyer_status,
+        })))
+}